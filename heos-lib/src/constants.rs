@@ -10,8 +10,18 @@
 ///
 
 use std::str;
+use std::time::Duration;
 
 pub(crate) const DEFAULT_PORT: u16 = 1255;
 pub(crate) const CMD_PREFIX: &'static str = "heos://";
 pub(crate) const CMD_POSTFIX: &'static str = "\r\n";
 pub(crate) const TARGET_URN: &'static str = "urn:schemas-denon-com:device:ACT-Denon:1";
+pub(crate) const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+pub(crate) const MSG_UNDER_PROCESS: &'static str = "command under process";
+pub(crate) const MAX_QUEUE_RANGE: u32 = 100;
+pub(crate) const MAX_BROWSE_RANGE: u32 = 100;
+pub(crate) const MAX_QUEUE_NAME_LEN: usize = 128;
+pub(crate) const MAX_SEARCH_LEN: usize = 128;
+pub(crate) const DEFAULT_HEARTBEAT: Duration = Duration::from_secs(30);
+pub(crate) const MIN_RECONNECT_BACKOFF: Duration = Duration::from_secs(1);
+pub(crate) const MAX_RECONNECT_BACKOFF: Duration = Duration::from_secs(60);
\ No newline at end of file
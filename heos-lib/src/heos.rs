@@ -10,7 +10,7 @@
 ///
 
 use std::str;
-use anyhow::{anyhow, Result};
+use crate::heos_error::{HeosError, Result};
 use async_stream::stream;
 use const_format::formatcp;
 use futures_util::Stream;
@@ -54,7 +54,7 @@ impl Heos {
                     if response.contains(TARGET_URN) {
                         Ok(response.to_string())
                     } else {
-                        Err(anyhow!("Target urn not found"))
+                        Err(HeosError::Parse("Target urn not found".into()))
                     }
                 }
 
@@ -89,7 +89,7 @@ impl Heos {
             None => {}
         }
 
-        Err(anyhow!("Invalid response"))
+        Err(HeosError::Parse("Invalid response".into()))
     }
 
     pub(crate) fn parse_location(location_str: &str) -> Result<String> {
@@ -109,7 +109,7 @@ impl Heos {
         if stop > start {
             Ok(location_str[start+1..stop].to_string())
         } else {
-            Err(anyhow!("Invalid location"))
+            Err(HeosError::Parse("Invalid location".into()))
         }
     }
 }
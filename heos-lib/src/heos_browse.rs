@@ -0,0 +1,240 @@
+///
+/// @package heos-dial
+///
+/// @file HEOS lib
+/// @copyright (c) 2024-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv3.
+/// See the file LICENSE for details.
+///
+
+use std::fmt::{Display, Formatter};
+
+#[derive(Default, Clone, PartialEq, Debug)]
+pub struct HeosSource {
+    pub name: String,
+    pub image_url: String,
+    pub source_type: String,
+    pub source_id: String,
+    pub available: bool,
+    pub service_username: Option<String>,
+}
+
+impl HeosSource {
+    /* Well-known source ids of the HEOS system */
+    pub const LOCAL_MEDIA: &'static str = "1024";
+    pub const PLAYLISTS: &'static str = "1025";
+    pub const HISTORY: &'static str = "1026";
+    pub const AUX_INPUT: &'static str = "1027";
+    pub const FAVORITES: &'static str = "1028";
+}
+
+impl Display for HeosSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.name, self.source_id)
+    }
+}
+
+#[derive(Default, Clone, PartialEq, Debug)]
+pub struct HeosContainer {
+    pub name: String,
+    pub image_url: String,
+    pub container_type: String,
+    pub container_id: String,
+    pub media_id: Option<String>,
+    pub playable: bool,
+}
+
+impl Display for HeosContainer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.name, self.container_id)
+    }
+}
+
+#[derive(Default, Clone, PartialEq, Debug)]
+pub struct HeosMediaItem {
+    pub name: String,
+    pub image_url: String,
+    pub media_type: String,
+    pub media_id: String,
+    pub artist_title: String,
+    pub album_title: String,
+    pub playable: bool,
+}
+
+impl Display for HeosMediaItem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.artist_title.is_empty() {
+            true => write!(f, "{}", self.name),
+            false => write!(f, "{} - {}", self.artist_title, self.name),
+        }
+    }
+}
+
+#[derive(Default, Clone, PartialEq, Debug)]
+pub struct HeosSearchCriteria {
+    pub name: String,
+    pub criteria_id: String,
+    pub wildcard: bool,
+    pub playable: bool,
+    pub container_id: Option<String>,
+}
+
+impl HeosSearchCriteria {
+    /* Results of playable criteria live in a container named after the search */
+    pub fn result_container_id(&self, search: &str) -> Option<String> {
+        self.container_id.as_ref()
+            .map(|container_id| format!("{}{}", container_id, search))
+    }
+}
+
+impl Display for HeosSearchCriteria {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub enum AddCriteria {
+    #[default]
+    PlayNow,
+    PlayNext,
+    AddToEnd,
+    ReplaceAndPlay,
+}
+
+impl Display for AddCriteria {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            AddCriteria::PlayNow => 1,
+            AddCriteria::PlayNext => 2,
+            AddCriteria::AddToEnd => 3,
+            AddCriteria::ReplaceAndPlay => 4,
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ServiceOption {
+    AddTrackToLibrary,
+    AddAlbumToLibrary,
+    AddStationToLibrary,
+    AddPlaylistToLibrary,
+    RemoveTrackFromLibrary,
+    RemoveAlbumFromLibrary,
+    RemoveStationFromLibrary,
+    RemovePlaylistFromLibrary,
+    ThumbsUp,
+    ThumbsDown,
+    CreateNewStation,
+    AddToFavorites,
+    RemoveFromFavorites,
+    Unknown(u32),
+}
+
+impl ServiceOption {
+    pub fn from_id(option_id: u32) -> Self {
+        match option_id {
+            1 => ServiceOption::AddTrackToLibrary,
+            2 => ServiceOption::AddAlbumToLibrary,
+            3 => ServiceOption::AddStationToLibrary,
+            4 => ServiceOption::AddPlaylistToLibrary,
+            5 => ServiceOption::RemoveTrackFromLibrary,
+            6 => ServiceOption::RemoveAlbumFromLibrary,
+            7 => ServiceOption::RemoveStationFromLibrary,
+            8 => ServiceOption::RemovePlaylistFromLibrary,
+            11 => ServiceOption::ThumbsUp,
+            12 => ServiceOption::ThumbsDown,
+            13 => ServiceOption::CreateNewStation,
+            19 => ServiceOption::AddToFavorites,
+            20 => ServiceOption::RemoveFromFavorites,
+            option_id => ServiceOption::Unknown(option_id),
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        match self {
+            ServiceOption::AddTrackToLibrary => 1,
+            ServiceOption::AddAlbumToLibrary => 2,
+            ServiceOption::AddStationToLibrary => 3,
+            ServiceOption::AddPlaylistToLibrary => 4,
+            ServiceOption::RemoveTrackFromLibrary => 5,
+            ServiceOption::RemoveAlbumFromLibrary => 6,
+            ServiceOption::RemoveStationFromLibrary => 7,
+            ServiceOption::RemovePlaylistFromLibrary => 8,
+            ServiceOption::ThumbsUp => 11,
+            ServiceOption::ThumbsDown => 12,
+            ServiceOption::CreateNewStation => 13,
+            ServiceOption::AddToFavorites => 19,
+            ServiceOption::RemoveFromFavorites => 20,
+            ServiceOption::Unknown(option_id) => *option_id,
+        }
+    }
+}
+
+impl Display for ServiceOption {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            ServiceOption::AddTrackToLibrary => "Add Track to Library",
+            ServiceOption::AddAlbumToLibrary => "Add Album to Library",
+            ServiceOption::AddStationToLibrary => "Add Station to Library",
+            ServiceOption::AddPlaylistToLibrary => "Add Playlist to Library",
+            ServiceOption::RemoveTrackFromLibrary => "Remove Track from Library",
+            ServiceOption::RemoveAlbumFromLibrary => "Remove Album from Library",
+            ServiceOption::RemoveStationFromLibrary => "Remove Station from Library",
+            ServiceOption::RemovePlaylistFromLibrary => "Remove Playlist from Library",
+            ServiceOption::ThumbsUp => "Thumbs Up",
+            ServiceOption::ThumbsDown => "Thumbs Down",
+            ServiceOption::CreateNewStation => "Create New Station",
+            ServiceOption::AddToFavorites => "Add to HEOS Favorites",
+            ServiceOption::RemoveFromFavorites => "Remove from HEOS Favorites",
+            ServiceOption::Unknown(_) => "Unknown option",
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ServiceOptionTarget<'a> {
+    NowPlaying,
+    Media {
+        source_id: &'a str,
+        media_id: &'a str,
+        name: Option<&'a str>,
+    },
+    Container {
+        source_id: &'a str,
+        container_id: &'a str,
+        name: Option<&'a str>,
+    },
+    Search {
+        source_id: &'a str,
+        name: &'a str,
+    },
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum HeosBrowseItem {
+    Source(HeosSource),
+    Container(HeosContainer),
+    Media(HeosMediaItem),
+}
+
+impl Display for HeosBrowseItem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HeosBrowseItem::Source(source) => source.fmt(f),
+            HeosBrowseItem::Container(container) => container.fmt(f),
+            HeosBrowseItem::Media(media) => media.fmt(f),
+        }
+    }
+}
+
+#[derive(Default, Clone, PartialEq, Debug)]
+pub struct HeosBrowseResult {
+    pub source_id: String,
+    pub container_id: Option<String>,
+    pub items: Vec<HeosBrowseItem>,
+    pub returned: u32,
+    pub count: u32,
+}
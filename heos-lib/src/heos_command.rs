@@ -9,15 +9,86 @@
 /// See the file LICENSE for details.
 ///
 
+use std::borrow::Cow;
 use std::fmt::Display;
-use anyhow::Result;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use crate::constants::{CMD_POSTFIX, CMD_PREFIX};
+use crate::heos_error::{HeosError, Result};
 use crate::heos_reply::HeosReply;
 
-#[derive(Default, Clone)]
+const KNOWN_COMMANDS: &[&str] = &[
+    "system/register_for_change_events",
+    "system/check_account",
+    "system/sign_in",
+    "system/sign_out",
+    "system/heart_beat",
+    "system/reboot",
+    "system/prettify_json_response",
+    "player/get_players",
+    "player/get_player_info",
+    "player/get_play_state",
+    "player/set_play_state",
+    "player/get_now_playing_media",
+    "player/get_volume",
+    "player/set_volume",
+    "player/volume_up",
+    "player/volume_down",
+    "player/get_mute",
+    "player/set_mute",
+    "player/toggle_mute",
+    "player/get_play_mode",
+    "player/set_play_mode",
+    "player/get_queue",
+    "player/play_queue",
+    "player/remove_from_queue",
+    "player/save_queue",
+    "player/clear_queue",
+    "player/move_queue_item",
+    "player/play_next",
+    "player/play_previous",
+    "player/set_quickselect",
+    "player/play_quickselect",
+    "player/get_quickselect_info",
+    "player/check_update",
+
+    /* Older firmware lists group commands in the player group */
+    "player/get_groups",
+    "player/get_group_info",
+    "player/set_group",
+
+    "group/get_groups",
+    "group/get_group_info",
+    "group/set_group",
+    "group/get_volume",
+    "group/set_volume",
+    "group/volume_up",
+    "group/volume_down",
+    "group/get_mute",
+    "group/set_mute",
+    "group/toggle_mute",
+    "browse/get_music_sources",
+    "browse/get_source_info",
+    "browse/browse",
+    "browse/get_search_criteria",
+    "browse/search",
+    "browse/play_stream",
+    "browse/play_preset",
+    "browse/play_input",
+    "browse/add_to_queue",
+    "browse/rename_playlist",
+    "browse/delete_playlist",
+    "browse/retrieve_metadata",
+    "browse/get_service_options",
+    "browse/set_service_option",
+    "browse/multi_search",
+];
+
+#[derive(Default, Clone, PartialEq, Debug)]
 pub struct HeosCommand<'a> {
-    group: Option<&'a str>,
-    cmd: Option<&'a str>,
-    attrs: Option<Vec<(&'a str, &'a str)>>,
+    group: Option<Cow<'a, str>>,
+    cmd: Option<Cow<'a, str>>,
+    attrs: Option<Vec<(Cow<'a, str>, Cow<'a, str>)>>,
 }
 
 impl<'a> HeosCommand<'a> {
@@ -29,60 +100,186 @@ impl<'a> HeosCommand<'a> {
         }
     }
 
-    pub fn group(mut self, cmd_group_string: &'a str) -> Self {
-        self.group = Some(cmd_group_string);
+    pub fn group(mut self, cmd_group_string: impl Into<Cow<'a, str>>) -> Self {
+        self.group = Some(cmd_group_string.into());
 
         self
     }
 
-    pub fn cmd(mut self, cmd_string: &'a str) -> Self {
-        self.cmd = Some(cmd_string);
+    pub fn cmd(mut self, cmd_string: impl Into<Cow<'a, str>>) -> Self {
+        self.cmd = Some(cmd_string.into());
 
         self
     }
 
-    pub fn attrs(mut self, attrs: Vec<(&'a str, &'a str)>) -> Self {
-        self.attrs = Some(attrs);
+    pub fn attrs<K, V>(mut self, attrs: Vec<(K, V)>) -> Self
+        where K: Into<Cow<'a, str>>, V: Into<Cow<'a, str>>
+    {
+        self.attrs = Some(attrs.into_iter()
+            .map(|(key, value)| (key.into(), value.into()))
+            .collect());
 
         self
     }
 
-    pub fn attr(mut self, key: &'a str, value: &'a str) -> Self {
+    pub fn attr(mut self, key: impl Into<Cow<'a, str>>, value: impl Into<Cow<'a, str>>) -> Self {
+        let kv = (key.into(), value.into());
+
         if let Some(attrs) = self.attrs.as_mut() {
-            attrs.push((key, value));
+            attrs.push(kv);
         } else {
-            self.attrs = Some(vec![(key, value)]);
+            self.attrs = Some(vec![kv]);
         }
 
         self
     }
 
+    pub fn get_attr(&self, key: &str) -> Option<&str> {
+        self.attrs.as_ref()?.iter()
+            .find(|(attr_key, _)| attr_key == key)
+            .map(|(_, value)| value.as_ref())
+    }
+
+    pub fn name(&self) -> String {
+        format!("{}/{}", self.group.as_deref().expect("Group missing"),
+                self.cmd.as_deref().expect("Cmd missing"))
+    }
+
     pub fn is_player_command(&self) -> bool {
-        Some("player") == self.group
+        Some("player") == self.group.as_deref()
+    }
+
+    pub fn is_known(&self) -> bool {
+        match (self.group.as_deref(), self.cmd.as_deref()) {
+            (Some(_), Some(_)) => KNOWN_COMMANDS.contains(&self.name().as_str()),
+            _ => false,
+        }
+    }
+
+    pub fn into_owned(self) -> HeosCommand<'static> {
+        HeosCommand {
+            group: self.group.map(|group| Cow::Owned(group.into_owned())),
+            cmd: self.cmd.map(|cmd| Cow::Owned(cmd.into_owned())),
+            attrs: self.attrs.map(|attrs| attrs.into_iter()
+                .map(|(key, value)| (Cow::Owned(key.into_owned()), Cow::Owned(value.into_owned())))
+                .collect()),
+        }
+    }
+}
+
+impl FromStr for HeosCommand<'static> {
+    type Err = HeosError;
+
+    fn from_str(cmd_str: &str) -> Result<Self> {
+        let line = cmd_str.trim_end_matches(CMD_POSTFIX)
+            .strip_prefix(CMD_PREFIX)
+            .ok_or_else(|| HeosError::Parse(format!("Command `{}` lacks prefix `{}`", cmd_str, CMD_PREFIX)))?;
+
+        let (name, attrs_str) = match line.split_once('?') {
+            Some((name, attrs_str)) => (name, Some(attrs_str)),
+            None => (line, None),
+        };
+
+        let (group, cmd) = match name.split_once('/') {
+            Some((group, cmd)) if !group.is_empty() && !cmd.is_empty() => (group, cmd),
+            _ => return Err(HeosError::Parse(format!("Command `{}` must be `group/cmd`", name))),
+        };
+
+        /* Refuse anything the devices wouldn't understand before sending */
+        if !KNOWN_COMMANDS.contains(&name) {
+            return Err(HeosError::UnknownCommand(name.into()));
+        }
+
+        let mut heos_cmd = HeosCommand::new()
+            .group(group.to_string())
+            .cmd(cmd.to_string());
+
+        for kv in attrs_str.unwrap_or_default().split('&').filter(|kv| !kv.is_empty()) {
+            let (key, value) = kv.split_once('=')
+                .ok_or_else(|| HeosError::Parse(format!("Attribute `{}` lacks a value", kv)))?;
+
+            heos_cmd = heos_cmd.attr(key.to_string(), decode_value(value));
+        }
+
+        Ok(heos_cmd)
     }
 }
 
-fn format_attributes(attrs: Option<&Vec<(&str, &str)>>) -> String {
-    if attrs.is_none() || attrs.unwrap().is_empty() {
-        "".into()
+pub(crate) fn check_range(name: &str, value: u8, range: RangeInclusive<u8>) -> Result<()> {
+    if range.contains(&value) {
+        Ok(())
     } else {
-        match attrs.unwrap().iter()
-            .map(|kv| { format!("{}={}", kv.0, kv.1) })
-            .reduce(|prev, next| { format!("{}&{}", prev, next) })
-        {
-            Some(result) => format!("?{}", result),
-            None => "".into()
+        Err(HeosError::InvalidArgument(
+            format!("Value `{}` of `{}` out of range {:?}", value, name, range)))
+    }
+}
+
+pub(crate) fn check_page_range(range: &RangeInclusive<u32>, max: u32) -> Result<()> {
+    if range.is_empty() || range.end() - range.start() >= max {
+        Err(HeosError::InvalidArgument(
+            format!("Range {:?} must cover 1 to {} items", range, max)))
+    } else {
+        Ok(())
+    }
+}
+
+pub(crate) fn format_range(range: &RangeInclusive<u32>) -> String {
+    format!("{},{}", range.start(), range.end())
+}
+
+pub(crate) fn encode_value(value: &str) -> String {
+    /* The spec only requires reserved characters of the query to be escaped */
+    value.chars()
+        .map(|c| match c {
+            '%' => "%25".into(),
+            '&' => "%26".into(),
+            '=' => "%3D".into(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+pub(crate) fn decode_value(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        /* Keep malformed sequences as they are */
+        let hex = bytes.get(i + 1..i + 3)
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            },
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            },
         }
     }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn format_attributes(attrs: Option<&Vec<(Cow<'_, str>, Cow<'_, str>)>>) -> String {
+    match attrs {
+        Some(attrs) if !attrs.is_empty() => format!("?{}", attrs.iter()
+            .map(|(key, value)| format!("{}={}", key, encode_value(value)))
+            .collect::<Vec<_>>()
+            .join("&")),
+        _ => "".into(),
+    }
 }
 
 impl<'a> Display for HeosCommand<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}{}/{}{}{}", crate::constants::CMD_PREFIX,
-               self.group.expect("Group missing"),
-               self.cmd.expect("Cmd missing"),
-               format_attributes(self.attrs.as_ref()),
-               crate::constants::CMD_POSTFIX)
+        write!(f, "{}{}{}{}", CMD_PREFIX, self.name(),
+               format_attributes(self.attrs.as_ref()), CMD_POSTFIX)
     }
 }
 
@@ -11,7 +11,10 @@
 
 #[cfg(test)]
 mod heos_commands_test {
-    use crate::heos_command::HeosCommand;
+    use crate::heos_command::{decode_value, encode_value, HeosCommand};
+    use crate::heos_error::HeosError;
+    use rstest::rstest;
+    use tokio::sync::mpsc;
     use pretty_assertions::assert_eq;
 
     #[test]
@@ -45,6 +48,108 @@ mod heos_commands_test {
         assert_eq!(CMD_SET_PLAY_STATE2, cmd3);
     }
 
+    #[test]
+    fn should_encode_attribute_values() {
+        let cmd = HeosCommand::new()
+            .group("browse")
+            .cmd("search")
+            .attr("sid", "1028")
+            .attr("search", "Rock & Roll = 100%")
+            .to_string();
+
+        assert_eq!("heos://browse/search?sid=1028&search=Rock %26 Roll %3D 100%25\r\n", cmd);
+    }
+
+    #[rstest]
+    #[case("Rock & Roll")]
+    #[case("a=b&c=d")]
+    #[case("100% Motörhead")]
+    #[case("http://example.com/stream?id=1&fmt=mp3")]
+    #[case("%26 already encoded")]
+    fn should_round_trip_attribute_values(#[case] value: &str) {
+        assert_eq!(decode_value(&encode_value(value)), value);
+    }
+
+    #[test]
+    fn should_keep_malformed_escapes() {
+        assert_eq!(decode_value("50%"), "50%");
+        assert_eq!(decode_value("%zz%4"), "%zz%4");
+        assert_eq!(decode_value("%+5"), "%+5");
+        assert_eq!(decode_value("%-1"), "%-1");
+        assert_eq!(decode_value("Mot%C3%B6rhead"), "Motörhead");
+    }
+
+    #[test]
+    fn should_parse_command_strings() {
+        let cmd: HeosCommand = "heos://player/set_volume?pid=844263156&level=20\r\n".parse()
+            .expect("Failed to parse command");
+
+        assert_eq!(cmd.name(), "player/set_volume");
+        assert_eq!(cmd.get_attr("pid"), Some("844263156"));
+        assert_eq!(cmd.get_attr("level"), Some("20"));
+        assert_eq!(cmd.to_string(), "heos://player/set_volume?pid=844263156&level=20\r\n");
+    }
+
+    #[rstest]
+    #[case("heos://player/get_players\r\n")]
+    #[case("heos://browse/search?sid=1028&search=Rock %26 Roll %3D 100%25\r\n")]
+    #[case("heos://browse/play_stream?pid=5&url=http://example.com/stream?id%3D1%26fmt%3Dmp3\r\n")]
+    fn should_round_trip_command_strings(#[case] line: &str) {
+        let cmd: HeosCommand = line.parse()
+            .expect("Failed to parse command");
+
+        assert_eq!(cmd.to_string(), line);
+    }
+
+    #[rstest]
+    #[case("player/get_players")]
+    #[case("heos://get_players")]
+    #[case("heos:///get_players")]
+    #[case("heos://player/set_volume?level")]
+    fn should_reject_malformed_command_strings(#[case] line: &str) {
+        assert!(matches!(line.parse::<HeosCommand>(), Err(HeosError::Parse(_))));
+    }
+
+    #[rstest]
+    #[case("heos://player/self_destruct")]
+    #[case("heos://speaker/get_volume?pid=5")]
+    #[case("heos://group/get_players")]
+    fn should_reject_unknown_commands(#[case] line: &str) {
+        assert!(matches!(line.parse::<HeosCommand>(), Err(HeosError::UnknownCommand(_))));
+    }
+
+    #[test]
+    fn should_know_legacy_group_commands() {
+        let cmd: HeosCommand = "heos://player/get_groups".parse()
+            .expect("Failed to parse command");
+
+        assert!(cmd.is_known());
+        assert!(!HeosCommand::new().group("player").cmd("get_everything").is_known());
+    }
+
+    #[tokio::test]
+    async fn should_send_owned_commands_across_tasks() {
+        let (sender, mut receiver) = mpsc::unbounded_channel::<HeosCommand<'static>>();
+
+        let level = 20.to_string();
+
+        /* Borrowed commands can be turned into owned ones */
+        let cmd = HeosCommand::new()
+            .group("player")
+            .cmd("set_volume")
+            .attr("level", &level)
+            .into_owned();
+
+        tokio::spawn(async move {
+            sender.send(cmd).expect("Failed to send command");
+        });
+
+        let cmd = receiver.recv().await
+            .expect("Failed to receive command");
+
+        assert_eq!(cmd.to_string(), "heos://player/set_volume?level=20\r\n");
+    }
+
     #[test]
     fn should_identify_player_commands() {
         let cmd1 = HeosCommand::new()
@@ -57,6 +162,6 @@ mod heos_commands_test {
             .group("group")
             .cmd("get_volume");
 
-        assert_eq!(false, cmd1.is_player_command());
+        assert_eq!(false, cmd2.is_player_command());
     }
 }
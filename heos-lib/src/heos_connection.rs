@@ -0,0 +1,320 @@
+///
+/// @package heos-dial
+///
+/// @file HEOS lib
+/// @copyright (c) 2024-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv3.
+/// See the file LICENSE for details.
+///
+
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+use async_stream::stream;
+use futures_util::Stream;
+use log::{error, warn};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::{broadcast, mpsc, oneshot, watch};
+use crate::constants::{DEFAULT_HEARTBEAT, DEFAULT_TIMEOUT, MAX_RECONNECT_BACKOFF, MIN_RECONNECT_BACKOFF};
+use crate::heos_command::HeosCommand;
+use crate::heos_error::{HeosError, Result};
+use crate::heos_event::HeosEvent;
+use crate::heos_reply::HeosReply;
+use crate::heos_state::ConnectionState;
+
+const EVENT_CAPACITY: usize = 64;
+const HEARTBEAT_CMD: &str = "system/heart_beat";
+const REGISTER_CMD: &str = "system/register_for_change_events";
+
+type HeosReplySender = oneshot::Sender<Result<String>>;
+
+#[derive(Debug)]
+struct HeosRequest {
+    name: String,
+    line: String,
+    reply: HeosReplySender,
+}
+
+enum SessionEnd {
+    Dropped,
+    Broken,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeosConnectionConfig {
+    pub heartbeat: Duration,
+    pub min_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for HeosConnectionConfig {
+    fn default() -> Self {
+        Self {
+            heartbeat: DEFAULT_HEARTBEAT,
+            min_backoff: MIN_RECONNECT_BACKOFF,
+            max_backoff: MAX_RECONNECT_BACKOFF,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HeosConnection {
+    requests: mpsc::UnboundedSender<HeosRequest>,
+    events: broadcast::Sender<HeosEvent>,
+    state: watch::Receiver<ConnectionState>,
+    timeout: Duration,
+}
+
+impl HeosConnection {
+    pub async fn connect(addr: &str) -> Result<Self> {
+        Self::connect_with_config(addr, HeosConnectionConfig::default()).await
+    }
+
+    pub async fn connect_with_config(addr: &str, config: HeosConnectionConfig) -> Result<Self> {
+        /* Powered off devices just don't answer */
+        let stream = tokio::time::timeout(DEFAULT_TIMEOUT, TcpStream::connect(addr)).await
+            .map_err(|_| HeosError::Timeout(addr.to_string()))??;
+
+        let (requests, receiver) = mpsc::unbounded_channel();
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+        let (state_sender, state) = watch::channel(ConnectionState::Connected);
+
+        tokio::spawn(Self::supervise(addr.to_string(), stream, config,
+                                     receiver, events.clone(), state_sender));
+
+        Ok(Self {
+            requests,
+            events,
+            state,
+            timeout: DEFAULT_TIMEOUT,
+        })
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+
+        self
+    }
+
+    pub async fn send(&self, cmd: &HeosCommand<'_>) -> Result<String> {
+        let (reply, receiver) = oneshot::channel();
+
+        self.requests.send(HeosRequest {
+            name: cmd.name(),
+            line: cmd.to_string(),
+            reply,
+        }).map_err(|_| HeosError::ConnectionClosed)?;
+
+        match tokio::time::timeout(self.timeout, receiver).await {
+            Ok(reply) => reply.map_err(|_| HeosError::ConnectionClosed)?,
+            Err(_) => Err(HeosError::Timeout(cmd.name())),
+        }
+    }
+
+    pub fn events(&self) -> impl Stream<Item = HeosEvent> {
+        /* Subscribe right away to not miss anything until the stream is polled */
+        let mut receiver = self.events.subscribe();
+
+        stream! {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => yield event,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        }
+    }
+
+    pub fn state(&self) -> watch::Receiver<ConnectionState> {
+        self.state.clone()
+    }
+
+    pub fn is_closed(&self) -> bool {
+        ConnectionState::Closed == *self.state.borrow()
+    }
+
+    async fn supervise(addr: String, mut stream: TcpStream, config: HeosConnectionConfig,
+                       mut requests: mpsc::UnboundedReceiver<HeosRequest>,
+                       events: broadcast::Sender<HeosEvent>, state: watch::Sender<ConnectionState>)
+    {
+        let mut registration = None;
+
+        while let SessionEnd::Broken = Self::run(stream, &config, &mut requests,
+                                                 &events, &mut registration).await
+        {
+            match Self::reconnect(&addr, &config, &mut requests, &state).await {
+                Some(reconnected) => {
+                    stream = reconnected;
+
+                    let _ = state.send(ConnectionState::Connected);
+                },
+
+                /* All handles are gone */
+                None => break,
+            }
+        }
+
+        /* Refuse further requests */
+        requests.close();
+
+        let _ = state.send(ConnectionState::Closed);
+    }
+
+    async fn reconnect(addr: &str, config: &HeosConnectionConfig,
+                       requests: &mut mpsc::UnboundedReceiver<HeosRequest>,
+                       state: &watch::Sender<ConnectionState>) -> Option<TcpStream>
+    {
+        let mut backoff = config.min_backoff;
+        let mut attempt = 0;
+
+        loop {
+            attempt += 1;
+
+            let _ = state.send(ConnectionState::Reconnecting { attempt });
+
+            /* Fail requests right away instead of letting them run into the timeout */
+            let sleep = tokio::time::sleep(backoff);
+            tokio::pin!(sleep);
+
+            loop {
+                tokio::select! {
+                    _ = &mut sleep => break,
+
+                    request = requests.recv() => match request {
+                        Some(request) => {
+                            let _ = request.reply.send(Err(HeosError::ConnectionClosed));
+                        },
+                        None => return None,
+                    },
+                }
+            }
+
+            if let Ok(Ok(stream)) = tokio::time::timeout(DEFAULT_TIMEOUT,
+                                                         TcpStream::connect(addr)).await
+            {
+                return Some(stream);
+            }
+
+            backoff = (backoff * 2).min(config.max_backoff);
+        }
+    }
+
+    async fn run(stream: TcpStream, config: &HeosConnectionConfig,
+                 requests: &mut mpsc::UnboundedReceiver<HeosRequest>,
+                 events: &broadcast::Sender<HeosEvent>,
+                 registration: &mut Option<String>) -> SessionEnd
+    {
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        /* Dropping pending senders on return wakes up all waiting callers */
+        let mut pending: HashMap<String, VecDeque<HeosReplySender>> = HashMap::new();
+
+        /* Skip the immediate first tick */
+        let mut heartbeat = tokio::time::interval_at(
+            tokio::time::Instant::now() + config.heartbeat, config.heartbeat);
+        let mut awaiting_heartbeat = false;
+
+        /* Devices forget about registrations once the connection is gone */
+        if let Some(line) = registration.as_ref() {
+            if writer.write_all(line.as_bytes()).await.is_err() {
+                return SessionEnd::Broken;
+            }
+        }
+
+        loop {
+            tokio::select! {
+                request = requests.recv() => match request {
+                    Some(request) => {
+                        if let Err(err) = writer.write_all(request.line.as_bytes()).await {
+                            let _ = request.reply.send(Err(HeosError::Io(err)));
+
+                            return SessionEnd::Broken;
+                        }
+
+                        if REGISTER_CMD == request.name {
+                            *registration = request.line.contains("enable=on")
+                                .then(|| request.line.clone());
+                        }
+
+                        pending.entry(request.name).or_default().push_back(request.reply);
+                    },
+
+                    /* All handles are gone */
+                    None => return SessionEnd::Dropped,
+                },
+
+                line = lines.next_line() => match line {
+                    Ok(Some(line)) => {
+                        if HEARTBEAT_CMD == gjson::get(&line, "heos.command").str() {
+                            awaiting_heartbeat = false;
+                        }
+
+                        Self::dispatch(line, &mut pending, events)
+                    },
+
+                    /* Connection closed or broken */
+                    _ => return SessionEnd::Broken,
+                },
+
+                _ = heartbeat.tick() => {
+                    /* Previous heartbeat went unanswered */
+                    if awaiting_heartbeat {
+                        return SessionEnd::Broken;
+                    }
+
+                    let line = HeosCommand::new()
+                        .group("system")
+                        .cmd("heart_beat")
+                        .to_string();
+
+                    if writer.write_all(line.as_bytes()).await.is_err() {
+                        return SessionEnd::Broken;
+                    }
+
+                    awaiting_heartbeat = true;
+                },
+            }
+        }
+    }
+
+    fn dispatch(line: String, pending: &mut HashMap<String, VecDeque<HeosReplySender>>,
+                events: &broadcast::Sender<HeosEvent>)
+    {
+        if HeosEvent::is_event(&line) {
+            match HeosEvent::parse(&line) {
+                Ok(event) => {
+                    let _ = events.send(event);
+                },
+                Err(err) => error!("Error parse event: {:#?}", err),
+            }
+
+            return;
+        }
+
+        /* Keep waiting for the actual reply */
+        if HeosReply::is_under_process(&line) {
+            return;
+        }
+
+        /* Replies are matched to the oldest outstanding command of the same name */
+        let name = gjson::get(&line, "heos.command").str().to_string();
+
+        /* Skip callers that timed out and dropped their receivers */
+        let maybe_reply = pending.get_mut(&name).and_then(|waiting| {
+            std::iter::from_fn(|| waiting.pop_front()).find(|reply| !reply.is_closed())
+        });
+
+        match maybe_reply {
+            Some(reply) => {
+                let _ = reply.send(Ok(line));
+            },
+
+            /* Replies to our own heartbeats and re-registrations */
+            None if HEARTBEAT_CMD == name || REGISTER_CMD == name => (),
+            None => warn!("Unexpected reply: {}", line),
+        }
+    }
+}
\ No newline at end of file
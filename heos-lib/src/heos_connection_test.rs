@@ -0,0 +1,341 @@
+///
+/// @package heos-dial
+///
+/// @file HEOS connection tests
+/// @copyright (c) 2024-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv3.
+/// See the file LICENSE for details.
+///
+
+#[cfg(test)]
+mod heos_connection_test {
+    use crate::heos_command::HeosCommand;
+    use crate::heos_connection::{HeosConnection, HeosConnectionConfig};
+    use crate::heos_error::HeosError;
+    use crate::heos_event::HeosEvent;
+    use crate::heos_mock::{as_line, mock_connection};
+    use crate::heos_reply::HeosReply;
+    use crate::heos_state::ConnectionState;
+    use crate::test_asset;
+    use futures_util::{pin_mut, StreamExt};
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+
+    fn test_config(heartbeat: Duration) -> HeosConnectionConfig {
+        HeosConnectionConfig {
+            heartbeat,
+            min_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(100),
+        }
+    }
+
+    async fn mock_listener() -> (TcpListener, String) {
+        let listener = TcpListener::bind("127.0.0.1:0").await
+            .expect("Failed to bind mock device");
+        let addr = listener.local_addr()
+            .expect("Failed to get mock address").to_string();
+
+        (listener, addr)
+    }
+
+    #[tokio::test]
+    async fn should_match_replies_to_commands() {
+        let (connection, _) = mock_connection(2, vec![
+            as_line(test_asset!("player_get_volume.json")),
+            as_line(test_asset!("get_players.json")),
+        ]).await;
+
+        let players_cmd = HeosCommand::new()
+            .group("player")
+            .cmd("get_players");
+        let volume_cmd = HeosCommand::new()
+            .group("player")
+            .cmd("get_volume");
+
+        /* Send both concurrently, replies arrive in reverse order */
+        let (players, volume) = tokio::join!(
+            connection.send(&players_cmd),
+            connection.send(&volume_cmd));
+
+        assert!(matches!(HeosReply::parse(&players.expect("Failed to get players"))
+            .expect("Failed to parse players"), HeosReply::Players { .. }));
+        assert!(matches!(HeosReply::parse(&volume.expect("Failed to get volume"))
+            .expect("Failed to parse volume"), HeosReply::Volume { .. }));
+    }
+
+    #[tokio::test]
+    async fn should_route_events_to_subscribers() {
+        let (connection, _) = mock_connection(1, vec![
+            as_line(test_asset!("event_player_volume_changed.json")),
+            as_line(test_asset!("player_get_volume.json")),
+        ]).await;
+
+        let events = connection.events();
+        pin_mut!(events);
+
+        let cmd = HeosCommand::new()
+            .group("player")
+            .cmd("get_volume");
+
+        let reply = connection.send(&cmd).await
+            .expect("Failed to send command");
+
+        assert!(matches!(HeosReply::parse(&reply)
+            .expect("Failed to parse volume"), HeosReply::Volume { .. }));
+
+        assert_eq!(events.next().await, Some(HeosEvent::PlayerVolumeChanged {
+            player_id: "844263156".into(),
+            level: 25,
+            mute: false,
+        }));
+    }
+
+    #[tokio::test]
+    async fn should_wait_for_command_under_process() {
+        let (connection, _) = mock_connection(1, vec![
+            as_line(test_asset!("command_under_process.json")),
+            as_line(test_asset!("get_players.json")),
+        ]).await;
+
+        let cmd = HeosCommand::new()
+            .group("player")
+            .cmd("get_players");
+
+        let reply = connection.send(&cmd).await
+            .expect("Failed to send command");
+
+        assert!(matches!(HeosReply::parse(&reply)
+            .expect("Failed to parse players"), HeosReply::Players { .. }));
+    }
+
+    #[tokio::test]
+    async fn should_time_out_without_completion() {
+        let (connection, _) = mock_connection(1, vec![
+            as_line(test_asset!("command_under_process.json")),
+        ]).await;
+
+        let connection = connection.with_timeout(Duration::from_millis(100));
+
+        let cmd = HeosCommand::new()
+            .group("player")
+            .cmd("get_players");
+
+        assert!(matches!(connection.send(&cmd).await, Err(HeosError::Timeout(_))));
+    }
+
+    #[tokio::test]
+    async fn should_not_hand_replies_to_timed_out_commands() {
+        let (listener, addr) = mock_listener().await;
+
+        /* Swallow the first command and answer the second */
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await
+                .expect("Failed to accept connection");
+            let (reader, mut writer) = stream.into_split();
+            let mut lines = BufReader::new(reader).lines();
+
+            lines.next_line().await.expect("Failed to read command");
+            lines.next_line().await.expect("Failed to read command");
+
+            writer.write_all(as_line(test_asset!("player_get_volume.json")).as_bytes()).await
+                .expect("Failed to write reply");
+
+            while let Ok(Some(_)) = lines.next_line().await {}
+        });
+
+        let connection = HeosConnection::connect(&addr).await
+            .expect("Failed to connect to mock device")
+            .with_timeout(Duration::from_millis(100));
+
+        let cmd = HeosCommand::new()
+            .group("player")
+            .cmd("get_volume");
+
+        assert!(matches!(connection.send(&cmd).await, Err(HeosError::Timeout(_))));
+
+        let reply = connection.send(&cmd).await
+            .expect("Failed to get own reply");
+
+        assert!(matches!(HeosReply::parse(&reply)
+            .expect("Failed to parse volume"), HeosReply::Volume { .. }));
+    }
+
+    #[tokio::test]
+    async fn should_fail_when_connection_closes() {
+        let listener = TcpListener::bind("127.0.0.1:0").await
+            .expect("Failed to bind mock device");
+        let addr = listener.local_addr()
+            .expect("Failed to get mock address").to_string();
+
+        /* Hang up right after the first command */
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await
+                .expect("Failed to accept connection");
+            let mut lines = BufReader::new(stream).lines();
+
+            lines.next_line().await.expect("Failed to read command");
+        });
+
+        let connection = HeosConnection::connect(&addr).await
+            .expect("Failed to connect to mock device");
+
+        let cmd = HeosCommand::new()
+            .group("player")
+            .cmd("get_volume");
+
+        assert!(matches!(connection.send(&cmd).await, Err(HeosError::ConnectionClosed)));
+
+        /* Mock device is gone for good, so this keeps trying */
+        connection.state().wait_for(|state| matches!(state, ConnectionState::Reconnecting { .. })).await
+            .expect("Failed to wait for reconnect");
+        assert!(!connection.is_closed());
+    }
+
+    #[tokio::test]
+    async fn should_reconnect_and_restore_registration() {
+        let (listener, addr) = mock_listener().await;
+        let (lines_tx, mut lines_rx) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            /* Reply to the registration and hang up */
+            let (stream, _) = listener.accept().await
+                .expect("Failed to accept connection");
+            let (reader, mut writer) = stream.into_split();
+            let mut lines = BufReader::new(reader).lines();
+
+            let line = lines.next_line().await.expect("Failed to read command");
+            lines_tx.send(line.unwrap_or_default()).expect("Failed to forward command");
+
+            writer.write_all(as_line(test_asset!("register_for_change_events.json")).as_bytes()).await
+                .expect("Failed to write reply");
+
+            drop(lines);
+            drop(writer);
+
+            /* Registration must be restored before anything else */
+            let (stream, _) = listener.accept().await
+                .expect("Failed to accept connection");
+            let (reader, mut writer) = stream.into_split();
+            let mut lines = BufReader::new(reader).lines();
+
+            let line = lines.next_line().await.expect("Failed to read command");
+            lines_tx.send(line.unwrap_or_default()).expect("Failed to forward command");
+
+            writer.write_all(as_line(test_asset!("register_for_change_events.json")).as_bytes()).await
+                .expect("Failed to write reply");
+            writer.write_all(as_line(test_asset!("event_player_volume_changed.json")).as_bytes()).await
+                .expect("Failed to write event");
+
+            while let Ok(Some(_)) = lines.next_line().await {}
+        });
+
+        let connection = HeosConnection::connect_with_config(&addr,
+            test_config(Duration::from_secs(30))).await
+            .expect("Failed to connect to mock device");
+
+        let events = connection.events();
+        pin_mut!(events);
+
+        let cmd = HeosCommand::new()
+            .group("system")
+            .cmd("register_for_change_events")
+            .attrs(vec![("enable", "on")]);
+
+        let reply = connection.send(&cmd).await
+            .expect("Failed to send command");
+
+        assert!(matches!(HeosReply::parse(&reply)
+            .expect("Failed to parse registration"), HeosReply::ChangeEvents { .. }));
+
+        assert_eq!(events.next().await, Some(HeosEvent::PlayerVolumeChanged {
+            player_id: "844263156".into(),
+            level: 25,
+            mute: false,
+        }));
+
+        let line = cmd.to_string();
+
+        assert_eq!(lines_rx.recv().await.as_deref(), Some(line.trim_end()));
+        assert_eq!(lines_rx.recv().await.as_deref(), Some(line.trim_end()));
+        assert_eq!(*connection.state().borrow(), ConnectionState::Connected);
+    }
+
+    #[tokio::test]
+    async fn should_keep_connection_with_heartbeat() {
+        let (listener, addr) = mock_listener().await;
+        let (lines_tx, mut lines_rx) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await
+                .expect("Failed to accept connection");
+            let (reader, mut writer) = stream.into_split();
+            let mut lines = BufReader::new(reader).lines();
+
+            while let Ok(Some(line)) = lines.next_line().await {
+                let _ = lines_tx.send(line);
+
+                writer.write_all(as_line(test_asset!("heart_beat.json")).as_bytes()).await
+                    .expect("Failed to write reply");
+            }
+        });
+
+        let connection = HeosConnection::connect_with_config(&addr,
+            test_config(Duration::from_millis(20))).await
+            .expect("Failed to connect to mock device");
+
+        for _ in 0..3 {
+            assert_eq!(lines_rx.recv().await.as_deref(), Some("heos://system/heart_beat"));
+        }
+
+        assert_eq!(*connection.state().borrow(), ConnectionState::Connected);
+    }
+
+    #[tokio::test]
+    async fn should_reconnect_on_missing_heartbeat() {
+        let (listener, addr) = mock_listener().await;
+        let (lines_tx, mut lines_rx) = mpsc::unbounded_channel();
+
+        /* Never reply, but keep the connection open */
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await
+                    .expect("Failed to accept connection");
+                let mut lines = BufReader::new(stream).lines();
+
+                while let Ok(Some(line)) = lines.next_line().await {
+                    let _ = lines_tx.send(line);
+                }
+            }
+        });
+
+        let connection = HeosConnection::connect_with_config(&addr,
+            test_config(Duration::from_millis(50))).await
+            .expect("Failed to connect to mock device");
+
+        let mut state = connection.state();
+
+        assert_eq!(lines_rx.recv().await.as_deref(), Some("heos://system/heart_beat"));
+
+        state.wait_for(|state| matches!(state, ConnectionState::Reconnecting { attempt: 1 })).await
+            .expect("Failed to wait for reconnect");
+        state.wait_for(ConnectionState::is_connected).await
+            .expect("Failed to wait for connection");
+    }
+
+    #[tokio::test]
+    async fn should_close_when_handles_are_gone() {
+        let (connection, _) = mock_connection(0, vec![]).await;
+
+        let mut state = connection.state();
+
+        drop(connection);
+
+        state.wait_for(|state| ConnectionState::Closed == *state).await
+            .expect("Failed to wait for close");
+    }
+}
@@ -10,13 +10,21 @@
 ///
 
 use std::fmt::{Display, Formatter};
-use anyhow::{anyhow, Result};
-use tokio::io;
-use tokio::net::TcpStream;
-use crate::constants::DEFAULT_PORT;
-use crate::heos_command::{HeosCommand, HeosCommandHandler};
-use crate::heos_media::HeosMedia;
+use std::time::Duration;
+use futures_util::Stream;
+use tokio::sync::watch;
+use std::ops::RangeInclusive;
+use crate::constants::{DEFAULT_PORT, MAX_BROWSE_RANGE, MAX_QUEUE_NAME_LEN, MAX_QUEUE_RANGE, MAX_SEARCH_LEN};
+use crate::heos_browse::{AddCriteria, HeosBrowseItem, HeosBrowseResult, HeosMediaItem, HeosSearchCriteria, HeosSource, ServiceOption, ServiceOptionTarget};
+use crate::heos_command::{check_page_range, check_range, format_range, HeosCommand, HeosCommandHandler};
+use crate::heos_connection::HeosConnection;
+use crate::heos_error::{HeosError, Result};
+use crate::heos_event::HeosEvent;
+use crate::heos_group::HeosGroup;
+use crate::heos_media::{HeosMedia, HeosProgress};
+use crate::heos_queue::HeosQueueItem;
 use crate::heos_reply::HeosReply;
+use crate::heos_state::{format_on_off, AccountState, ConnectionState, parse_on_off, HeosInput, PlayState, RepeatMode, ShuffleMode};
 
 #[derive(Debug, Default)]
 pub struct HeosDevice {
@@ -26,8 +34,13 @@ pub struct HeosDevice {
     pub player_id: String,
     pub group_id: String,
     pub volume: u16,
+    pub muted: bool,
+    pub repeat: RepeatMode,
+    pub shuffle: ShuffleMode,
     pub media: Option<HeosMedia>,
-    pub stream: Option<TcpStream>,
+    pub progress: Option<HeosProgress>,
+    pub connection: Option<HeosConnection>,
+    pub timeout: Option<Duration>,
 }
 
 impl HeosDevice {
@@ -39,20 +52,33 @@ impl HeosDevice {
             player_id: pid.into(),
             group_id: Default::default(),
             volume: 0,
+            muted: false,
+            repeat: RepeatMode::default(),
+            shuffle: ShuffleMode::default(),
             media: None,
-            stream: None,
+            progress: None,
+            connection: None,
+            timeout: None,
         })
     }
 
     pub async fn connect(&mut self) -> Result<()> {
-        /* Sanity check to prevent re-connection */
-        Ok(match self.stream {
-            Some(_) => (),
-            None => {
-                self.stream = Some(TcpStream::connect(
-                    format!("{}:{}", self.base_url, DEFAULT_PORT)).await?)
-            }
-        })
+        /* Connections heal themselves, so only connect once */
+        if self.connection.is_none() {
+            let connection = HeosConnection::connect(
+                &format!("{}:{}", self.base_url, DEFAULT_PORT)).await?;
+
+            self.connection = Some(match self.timeout {
+                Some(timeout) => connection.with_timeout(timeout),
+                None => connection,
+            })
+        }
+
+        Ok(())
+    }
+
+    pub fn connection_state(&self) -> Option<watch::Receiver<ConnectionState>> {
+        self.connection.as_ref().map(|connection| connection.state())
     }
 
     pub async fn update_info(&mut self) -> Result<()> {
@@ -69,64 +95,830 @@ impl HeosDevice {
                 self.name = device.name;
                 self.player_id = device.player_id;
             }
-        } else if let HeosReply::Error(_, _, message) = reply {
-            return Err(anyhow!(message.get("text")
-                .expect("Expected error test to be set").to_string()));
+        } else if let HeosReply::Error(..) = reply {
+            return Err(reply.into_error());
         }
 
         Ok(())
     }
 
     pub async fn update_volume(&mut self) -> Result<()> {
+        self.volume = self.get_volume().await?.into();
+
+        Ok(())
+    }
+
+    pub async fn update_media(&mut self) -> Result<()> {
         self.connect().await?;
 
         let cmd = HeosCommand::new()
             .group("player")
-            .cmd("get_volume");
+            .cmd("get_now_playing_media");
 
         let reply = self.send_command(&cmd).await?;
 
-        if let HeosReply::Volume(success, attrs) = reply {
+        if let HeosReply::PlayingMedia(success, media) = reply {
             if success {
-                self.volume = attrs.get("level").unwrap().parse::<u16>()?;
+                self.media = media;
             }
-        } else if let HeosReply::Error(_, _, message) = reply {
-            return Err(anyhow!(message.get("text")
-                .expect("Expected error text to be set").to_string()));
+        } else if let HeosReply::Error(..) = reply {
+            return Err(reply.into_error());
         }
 
         Ok(())
     }
 
-    pub async fn update_media(&mut self) -> Result<()> {
-        self.connect().await?;
+    pub async fn update_mute(&mut self) -> Result<()> {
+        self.get_mute().await?;
+
+        Ok(())
+    }
+
+    pub async fn update_play_mode(&mut self) -> Result<()> {
+        self.get_play_mode().await?;
 
+        Ok(())
+    }
+
+    pub fn update_progress(&mut self, cur_pos: u64, duration: u64) {
+        let state = self.progress.as_ref()
+            .map(|progress| progress.state)
+            .unwrap_or(PlayState::Play);
+
+        let mut progress = HeosProgress::new(cur_pos, duration);
+
+        progress.state = state;
+
+        self.progress = Some(progress);
+    }
+
+    pub fn update_play_state(&mut self, state: PlayState) {
+        if let Some(progress) = self.progress.as_mut() {
+            progress.set_state(state);
+        }
+    }
+
+    pub async fn get_play_state(&mut self) -> Result<PlayState> {
         let cmd = HeosCommand::new()
             .group("player")
-            .cmd("get_now_playing_media");
+            .cmd("get_play_state");
 
-        let reply = self.send_command(&cmd).await?;
+        match self.send_command(&cmd).await? {
+            HeosReply::PlayState(true, attrs) => {
+                let state = HeosReply::get_attr(&attrs, "state")?.parse()?;
 
-        if let HeosReply::PlayingMedia(success, attrs) = reply {
-            if success {
-                let mut media = HeosMedia::default();
+                self.update_play_state(state);
+
+                Ok(state)
+            },
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn set_play_state(&mut self, state: PlayState) -> Result<()> {
+        let state_str = state.to_string();
+
+        let cmd = HeosCommand::new()
+            .group("player")
+            .cmd("set_play_state")
+            .attr("state", &state_str);
+
+        match self.send_command(&cmd).await? {
+            HeosReply::PlayState(true, _) => {
+                self.update_play_state(state);
+
+                Ok(())
+            },
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn get_play_mode(&mut self) -> Result<(RepeatMode, ShuffleMode)> {
+        let cmd = HeosCommand::new()
+            .group("player")
+            .cmd("get_play_mode");
+
+        match self.send_command(&cmd).await? {
+            HeosReply::PlayMode(true, attrs) => {
+                self.repeat = HeosReply::get_attr(&attrs, "repeat")?.parse()?;
+                self.shuffle = HeosReply::get_attr(&attrs, "shuffle")?.parse()?;
+
+                Ok((self.repeat, self.shuffle))
+            },
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn set_play_mode(&mut self, repeat: RepeatMode, shuffle: ShuffleMode) -> Result<()> {
+        let repeat_str = repeat.to_string();
+        let shuffle_str = shuffle.to_string();
+
+        let cmd = HeosCommand::new()
+            .group("player")
+            .cmd("set_play_mode")
+            .attr("repeat", &repeat_str)
+            .attr("shuffle", &shuffle_str);
+
+        match self.send_command(&cmd).await? {
+            HeosReply::PlayMode(true, _) => {
+                self.repeat = repeat;
+                self.shuffle = shuffle;
+
+                Ok(())
+            },
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn get_volume(&mut self) -> Result<u8> {
+        let cmd = HeosCommand::new()
+            .group("player")
+            .cmd("get_volume");
+
+        match self.send_command(&cmd).await? {
+            HeosReply::Volume(true, attrs) => {
+                let level = HeosReply::get_attr(&attrs, "level")?.parse::<u8>()?;
+
+                self.volume = level.into();
+
+                Ok(level)
+            },
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn set_volume(&mut self, level: u8) -> Result<()> {
+        check_range("level", level, 0..=100)?;
+
+        let level_str = level.to_string();
+
+        let cmd = HeosCommand::new()
+            .group("player")
+            .cmd("set_volume")
+            .attr("level", &level_str);
+
+        match self.send_command(&cmd).await? {
+            HeosReply::Volume(true, _) => {
+                self.volume = level.into();
+
+                Ok(())
+            },
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn volume_up(&mut self, step: u8) -> Result<()> {
+        check_range("step", step, 1..=10)?;
+
+        let step_str = step.to_string();
+
+        let cmd = HeosCommand::new()
+            .group("player")
+            .cmd("volume_up")
+            .attr("step", &step_str);
+
+        match self.send_command(&cmd).await? {
+            /* Device reports the new level with a volume changed event */
+            HeosReply::Volume(true, _) => Ok(()),
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn volume_down(&mut self, step: u8) -> Result<()> {
+        check_range("step", step, 1..=10)?;
+
+        let step_str = step.to_string();
+
+        let cmd = HeosCommand::new()
+            .group("player")
+            .cmd("volume_down")
+            .attr("step", &step_str);
+
+        match self.send_command(&cmd).await? {
+            /* Device reports the new level with a volume changed event */
+            HeosReply::Volume(true, _) => Ok(()),
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn get_mute(&mut self) -> Result<bool> {
+        let cmd = HeosCommand::new()
+            .group("player")
+            .cmd("get_mute");
+
+        match self.send_command(&cmd).await? {
+            HeosReply::Mute(true, attrs) => {
+                self.muted = parse_on_off(HeosReply::get_attr(&attrs, "state")?)?;
+
+                Ok(self.muted)
+            },
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn set_mute(&mut self, mute: bool) -> Result<()> {
+        let cmd = HeosCommand::new()
+            .group("player")
+            .cmd("set_mute")
+            .attr("state", format_on_off(mute));
+
+        match self.send_command(&cmd).await? {
+            HeosReply::Mute(true, _) => {
+                self.muted = mute;
+
+                Ok(())
+            },
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn toggle_mute(&mut self) -> Result<()> {
+        let cmd = HeosCommand::new()
+            .group("player")
+            .cmd("toggle_mute");
+
+        match self.send_command(&cmd).await? {
+            HeosReply::Mute(true, _) => {
+                self.muted = !self.muted;
+
+                Ok(())
+            },
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn play_next(&mut self) -> Result<()> {
+        let cmd = HeosCommand::new()
+            .group("player")
+            .cmd("play_next");
+
+        match self.send_command(&cmd).await? {
+            HeosReply::PlayAction(true, _) => Ok(()),
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn play_previous(&mut self) -> Result<()> {
+        let cmd = HeosCommand::new()
+            .group("player")
+            .cmd("play_previous");
+
+        match self.send_command(&cmd).await? {
+            HeosReply::PlayAction(true, _) => Ok(()),
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn get_queue(&mut self, range: RangeInclusive<u32>) -> Result<Vec<HeosQueueItem>> {
+        check_page_range(&range, MAX_QUEUE_RANGE)?;
+
+        let range_str = format_range(&range);
+
+        let cmd = HeosCommand::new()
+            .group("player")
+            .cmd("get_queue")
+            .attr("range", &range_str);
+
+        match self.send_command(&cmd).await? {
+            HeosReply::Queue(true, _, items) => Ok(items),
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn play_queue(&mut self, queue_id: u32) -> Result<()> {
+        let qid_str = queue_id.to_string();
+
+        let cmd = HeosCommand::new()
+            .group("player")
+            .cmd("play_queue")
+            .attr("qid", &qid_str);
+
+        self.send_queue_command(&cmd).await
+    }
+
+    pub async fn remove_from_queue(&mut self, queue_ids: &[u32]) -> Result<()> {
+        let qids_str = format_queue_ids("queue_ids", queue_ids)?;
 
-                media.artist_title = attrs.get("artist").cloned().unwrap_or_default();
-                media.song_title = attrs.get("album").cloned().unwrap_or_default();
-                media.album_title = attrs.get("song").cloned().unwrap_or_default();
-                media.image_url = attrs.get("image_url").cloned().unwrap_or_default();
+        let cmd = HeosCommand::new()
+            .group("player")
+            .cmd("remove_from_queue")
+            .attr("qid", &qids_str);
+
+        self.send_queue_command(&cmd).await
+    }
+
+    pub async fn save_queue(&mut self, name: &str) -> Result<()> {
+        if name.is_empty() || name.chars().count() > MAX_QUEUE_NAME_LEN {
+            return Err(HeosError::InvalidArgument(
+                format!("Playlist name must have 1 to {} characters", MAX_QUEUE_NAME_LEN)));
+        }
+
+        let cmd = HeosCommand::new()
+            .group("player")
+            .cmd("save_queue")
+            .attr("name", name);
+
+        self.send_queue_command(&cmd).await
+    }
+
+    pub async fn clear_queue(&mut self) -> Result<()> {
+        let cmd = HeosCommand::new()
+            .group("player")
+            .cmd("clear_queue");
+
+        self.send_queue_command(&cmd).await
+    }
+
+    pub async fn move_queue_item(&mut self, queue_ids: &[u32], dest_queue_id: u32) -> Result<()> {
+        let sqids_str = format_queue_ids("queue_ids", queue_ids)?;
+        let dqid_str = dest_queue_id.to_string();
+
+        let cmd = HeosCommand::new()
+            .group("player")
+            .cmd("move_queue_item")
+            .attr("sqid", &sqids_str)
+            .attr("dqid", &dqid_str);
 
-                self.media = Some(media);
+        self.send_queue_command(&cmd).await
+    }
+
+    pub async fn get_music_sources(&mut self) -> Result<Vec<HeosSource>> {
+        let cmd = HeosCommand::new()
+            .group("browse")
+            .cmd("get_music_sources");
+
+        match self.send_command(&cmd).await? {
+            HeosReply::MusicSources(true, sources) => Ok(sources),
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn get_source_info(&mut self, source_id: &str) -> Result<HeosSource> {
+        let cmd = HeosCommand::new()
+            .group("browse")
+            .cmd("get_source_info")
+            .attr("sid", source_id);
+
+        match self.send_command(&cmd).await? {
+            HeosReply::SourceInfo(true, source) => Ok(source),
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn browse(&mut self, source_id: &str, container_id: Option<&str>,
+                        range: Option<RangeInclusive<u32>>) -> Result<HeosBrowseResult>
+    {
+        let range_str = match range {
+            Some(range) => {
+                check_page_range(&range, MAX_BROWSE_RANGE)?;
+
+                Some(format_range(&range))
+            },
+            None => None,
+        };
+
+        let mut cmd = HeosCommand::new()
+            .group("browse")
+            .cmd("browse")
+            .attr("sid", source_id);
+
+        if let Some(container_id) = container_id {
+            cmd = cmd.attr("cid", container_id);
+        }
+
+        if let Some(range_str) = range_str.as_ref() {
+            cmd = cmd.attr("range", range_str);
+        }
+
+        match self.send_command(&cmd).await? {
+            HeosReply::Browse(true, message, items) => Ok(HeosBrowseResult {
+                source_id: source_id.into(),
+                container_id: container_id.map(String::from),
+                returned: message.get("returned")
+                    .map_or(Ok(items.len() as u32), |returned| returned.parse())?,
+                count: message.get("count")
+                    .map_or(Ok(items.len() as u32), |count| count.parse())?,
+                items,
+            }),
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn get_search_criteria(&mut self, source_id: &str) -> Result<Vec<HeosSearchCriteria>> {
+        let cmd = HeosCommand::new()
+            .group("browse")
+            .cmd("get_search_criteria")
+            .attr("sid", source_id);
+
+        match self.send_command(&cmd).await? {
+            HeosReply::SearchCriteria(true, criteria) => Ok(criteria),
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn search(&mut self, source_id: &str, criteria_id: &str, search: &str,
+                        range: Option<RangeInclusive<u32>>) -> Result<HeosBrowseResult>
+    {
+        check_search(search)?;
+
+        let range_str = match range {
+            Some(range) => {
+                check_page_range(&range, MAX_BROWSE_RANGE)?;
+
+                Some(format_range(&range))
+            },
+            None => None,
+        };
+
+        let mut cmd = HeosCommand::new()
+            .group("browse")
+            .cmd("search")
+            .attr("sid", source_id)
+            .attr("search", search)
+            .attr("scid", criteria_id);
+
+        if let Some(range_str) = range_str.as_ref() {
+            cmd = cmd.attr("range", range_str);
+        }
+
+        self.send_search_command(&cmd, source_id).await
+    }
+
+    pub async fn multi_search(&mut self, search: &str, source_ids: &[&str],
+                              criteria_ids: &[&str]) -> Result<HeosBrowseResult>
+    {
+        check_search(search)?;
+
+        let sids_str = source_ids.join(",");
+        let scids_str = criteria_ids.join(",");
+
+        let mut cmd = HeosCommand::new()
+            .group("browse")
+            .cmd("multi_search")
+            .attr("search", search);
+
+        /* Omitting either list searches everything */
+        if !source_ids.is_empty() {
+            cmd = cmd.attr("sid", &sids_str);
+        }
+
+        if !criteria_ids.is_empty() {
+            cmd = cmd.attr("scid", &scids_str);
+        }
+
+        self.send_search_command(&cmd, &sids_str).await
+    }
+
+    async fn send_search_command(&mut self, cmd: &HeosCommand<'_>, source_id: &str) -> Result<HeosBrowseResult> {
+        match self.send_command(cmd).await? {
+            HeosReply::Search(true, message, items) => Ok(HeosBrowseResult {
+                source_id: source_id.into(),
+                container_id: None,
+                returned: message.get("returned")
+                    .map_or(Ok(items.len() as u32), |returned| returned.parse())?,
+                count: message.get("count")
+                    .map_or(Ok(items.len() as u32), |count| count.parse())?,
+                items,
+            }),
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn get_favorites(&mut self) -> Result<Vec<HeosMediaItem>> {
+        let result = self.browse(HeosSource::FAVORITES, None, None).await?;
+
+        Ok(result.items.into_iter()
+            .filter_map(|item| match item {
+                HeosBrowseItem::Media(media) => Some(media),
+                _ => None,
+            })
+            .collect())
+    }
+
+    pub async fn play_preset(&mut self, preset: u8) -> Result<()> {
+        check_range("preset", preset, 1..=u8::MAX)?;
+
+        let preset_str = preset.to_string();
+        let player_id = self.player_id.clone();
+
+        /* Browse commands don't get the player id appended */
+        let cmd = HeosCommand::new()
+            .group("browse")
+            .cmd("play_preset")
+            .attr("pid", &player_id)
+            .attr("preset", &preset_str);
+
+        self.send_play_command(&cmd).await
+    }
+
+    pub async fn play_stream(&mut self, url: &str) -> Result<()> {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(HeosError::InvalidArgument(format!("Url `{}` is no http stream", url)));
+        }
+
+        let player_id = self.player_id.clone();
+
+        let cmd = HeosCommand::new()
+            .group("browse")
+            .cmd("play_stream")
+            .attr("pid", &player_id)
+            .attr("url", url);
+
+        self.send_play_command(&cmd).await
+    }
+
+    pub async fn play_input(&mut self, input: HeosInput, source_player_id: Option<&str>) -> Result<()> {
+        let input_str = input.to_string();
+        let player_id = self.player_id.clone();
+
+        let mut cmd = HeosCommand::new()
+            .group("browse")
+            .cmd("play_input")
+            .attr("pid", &player_id);
+
+        /* Play the input of another player */
+        if let Some(source_player_id) = source_player_id {
+            cmd = cmd.attr("spid", source_player_id);
+        }
+
+        cmd = cmd.attr("input", &input_str);
+
+        self.send_play_command(&cmd).await
+    }
+
+    async fn send_play_command(&mut self, cmd: &HeosCommand<'_>) -> Result<()> {
+        match self.send_command(cmd).await? {
+            HeosReply::PlayAction(true, _) => Ok(()),
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn add_to_queue(&mut self, source_id: &str, container_id: &str,
+                              media_id: Option<&str>, criteria: AddCriteria) -> Result<()>
+    {
+        let criteria_str = criteria.to_string();
+        let player_id = self.player_id.clone();
+
+        /* Browse commands don't get the player id appended */
+        let mut cmd = HeosCommand::new()
+            .group("browse")
+            .cmd("add_to_queue")
+            .attr("pid", &player_id)
+            .attr("sid", source_id)
+            .attr("cid", container_id);
+
+        if let Some(media_id) = media_id {
+            cmd = cmd.attr("mid", media_id);
+        }
+
+        cmd = cmd.attr("aid", &criteria_str);
+
+        self.send_queue_command(&cmd).await
+    }
+
+    async fn send_queue_command(&mut self, cmd: &HeosCommand<'_>) -> Result<()> {
+        match self.send_command(cmd).await? {
+            HeosReply::QueueAction(true, _) => Ok(()),
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn get_service_options(&mut self, source_id: &str) -> Result<Vec<ServiceOption>> {
+        let cmd = HeosCommand::new()
+            .group("browse")
+            .cmd("get_service_options")
+            .attr("sid", source_id);
+
+        match self.send_command(&cmd).await? {
+            HeosReply::ServiceOptions(true, options) => Ok(options),
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn set_service_option(&mut self, option: ServiceOption,
+                                    target: ServiceOptionTarget<'_>) -> Result<()>
+    {
+        let option_str = option.id().to_string();
+        let player_id = self.player_id.clone();
+
+        let mut cmd = HeosCommand::new()
+            .group("browse")
+            .cmd("set_service_option");
+
+        match target {
+            ServiceOptionTarget::NowPlaying => {
+                let media = self.media.clone()
+                    .ok_or_else(|| HeosError::InvalidArgument("Nothing is playing".into()))?;
+
+                let source_id = media.source_type.sid().to_string();
+
+                /* Options of the current media are addressed via the player */
+                cmd = match option {
+                    ServiceOption::AddToFavorites => cmd
+                        .attr("option", &option_str)
+                        .attr("pid", &player_id),
+                    ServiceOption::ThumbsUp | ServiceOption::ThumbsDown => cmd
+                        .attr("sid", &source_id)
+                        .attr("option", &option_str)
+                        .attr("pid", &player_id),
+                    ServiceOption::AddAlbumToLibrary | ServiceOption::RemoveAlbumFromLibrary => cmd
+                        .attr("sid", &source_id)
+                        .attr("option", &option_str)
+                        .attr("cid", &media.album_id),
+                    _ => cmd
+                        .attr("sid", &source_id)
+                        .attr("option", &option_str)
+                        .attr("mid", &media.media_id),
+                };
+
+                self.send_service_option_command(&cmd).await
+            },
+            ServiceOptionTarget::Media { source_id, media_id, name } => {
+                /* Favorites are removed by media id only */
+                if ServiceOption::RemoveFromFavorites != option {
+                    cmd = cmd.attr("sid", source_id);
+                }
+
+                cmd = cmd
+                    .attr("option", &option_str)
+                    .attr("mid", media_id);
+
+                if let Some(name) = name {
+                    cmd = cmd.attr("name", name);
+                }
+
+                self.send_service_option_command(&cmd).await
+            },
+            ServiceOptionTarget::Container { source_id, container_id, name } => {
+                cmd = cmd
+                    .attr("sid", source_id)
+                    .attr("option", &option_str)
+                    .attr("cid", container_id);
+
+                if let Some(name) = name {
+                    cmd = cmd.attr("name", name);
+                }
+
+                self.send_service_option_command(&cmd).await
+            },
+            ServiceOptionTarget::Search { source_id, name } => {
+                check_search(name)?;
+
+                cmd = cmd
+                    .attr("sid", source_id)
+                    .attr("option", &option_str)
+                    .attr("name", name);
+
+                self.send_service_option_command(&cmd).await
+            },
+        }
+    }
+
+    async fn send_service_option_command(&mut self, cmd: &HeosCommand<'_>) -> Result<()> {
+        match self.send_command(cmd).await? {
+            HeosReply::ServiceOptionSet(true, _) => Ok(()),
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn set_group(&mut self, member_ids: &[&str]) -> Result<Option<HeosGroup>> {
+        /* A single pid dissolves the group, that's what ungroup is for */
+        if member_ids.is_empty() {
+            return Err(HeosError::InvalidArgument("Groups need at least one member".into()));
+        }
+
+        if member_ids.contains(&self.player_id.as_str()) {
+            return Err(HeosError::InvalidArgument(
+                format!("Leader `{}` can't be a member as well", self.player_id)));
+        }
+
+        self.send_set_group(member_ids).await
+    }
+
+    pub async fn ungroup(&mut self) -> Result<()> {
+        self.send_set_group(&[]).await?;
+
+        Ok(())
+    }
+
+    async fn send_set_group(&mut self, member_ids: &[&str]) -> Result<Option<HeosGroup>> {
+        /* Leader goes first, a single pid dissolves the group */
+        let pids_str = std::iter::once(self.player_id.as_str())
+            .chain(member_ids.iter().copied())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let cmd = HeosCommand::new()
+            .group("group")
+            .cmd("set_group")
+            .attr("pid", &pids_str);
+
+        match self.send_command(&cmd).await? {
+            HeosReply::GroupSet(true, attrs) => match attrs.get("gid") {
+                Some(group_id) => {
+                    let mut group = HeosGroup::new(
+                        attrs.get("name").map_or("", |name| name.as_str()), group_id);
+
+                    group.leader = Some(self.clone());
+
+                    self.group_id = group_id.clone();
+
+                    Ok(Some(group))
+                },
+                None => {
+                    self.group_id = Default::default();
+
+                    Ok(None)
+                },
+            },
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn check_account(&mut self) -> Result<AccountState> {
+        let cmd = HeosCommand::new()
+            .group("system")
+            .cmd("check_account");
+
+        self.send_account_command(&cmd).await
+    }
+
+    pub async fn sign_in(&mut self, user_name: &str, password: &str) -> Result<AccountState> {
+        if user_name.is_empty() || password.is_empty() {
+            return Err(HeosError::InvalidArgument("User name and password must not be empty".into()));
+        }
+
+        let cmd = HeosCommand::new()
+            .group("system")
+            .cmd("sign_in")
+            .attr("un", user_name)
+            .attr("pw", password);
+
+        self.send_account_command(&cmd).await
+    }
+
+    pub async fn sign_out(&mut self) -> Result<AccountState> {
+        let cmd = HeosCommand::new()
+            .group("system")
+            .cmd("sign_out");
+
+        self.send_account_command(&cmd).await
+    }
+
+    async fn send_account_command(&mut self, cmd: &HeosCommand<'_>) -> Result<AccountState> {
+        match self.send_command(cmd).await? {
+            HeosReply::Account(true, state) => Ok(state),
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn events(&mut self) -> Result<impl Stream<Item = HeosEvent>> {
+        self.connect().await?;
+
+        /* Subscribe before registering to catch the first events */
+        let events = self.connection.as_ref()
+            .expect("Expected connection to be set")
+            .events();
+
+        let cmd = HeosCommand::new()
+            .group("system")
+            .cmd("register_for_change_events")
+            .attr("enable", "on");
+
+        let reply = self.send_command(&cmd).await?;
+
+        if let HeosReply::ChangeEvents(success, _) = reply {
+            if !success {
+                return Err(HeosError::UnexpectedReply("Failed to register for change events".into()));
             }
-        } else if let HeosReply::Error(_, _, message) = reply {
-            return Err(anyhow!(message.get("text")
-                .expect("Expected error text to be set").to_string()));
+        } else if let HeosReply::Error(..) = reply {
+            return Err(reply.into_error());
         }
 
+        Ok(events)
+    }
+}
+
+fn check_search(search: &str) -> Result<()> {
+    if search.is_empty() || search.chars().count() > MAX_SEARCH_LEN {
+        Err(HeosError::InvalidArgument(
+            format!("Search string must have 1 to {} characters", MAX_SEARCH_LEN)))
+    } else {
         Ok(())
     }
 }
 
+fn format_queue_ids(name: &str, queue_ids: &[u32]) -> Result<String> {
+    if queue_ids.is_empty() {
+        return Err(HeosError::InvalidArgument(format!("`{}` must not be empty", name)));
+    }
+
+    Ok(queue_ids.iter()
+        .map(|qid| qid.to_string())
+        .collect::<Vec<_>>()
+        .join(","))
+}
+
 impl HeosCommandHandler for HeosDevice {
     async fn send_command<'a>(&mut self, cmd: &HeosCommand<'a>) -> Result<HeosReply> {
         self.connect().await?;
@@ -138,39 +930,10 @@ impl HeosCommandHandler for HeosDevice {
             dev_cmd = dev_cmd.attr("pid", self.player_id.as_str());
         };
 
-        match self.stream.as_ref() {
-            Some(stream) => {
-                stream.try_write(dev_cmd.to_string().as_bytes())?;
-
-                let mut buf = Vec::with_capacity(2048);
-
-                loop {
-                    stream.readable().await?;
-
-                    match stream.try_read_buf(&mut buf) {
-                        Ok(0) => break,
-                        Ok(_n) => {
-                            #[cfg(test)]
-                            println!("Read {} bytes", _n);
-                        }
-                        Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
-                            if '\r' == char::from(buf[buf.len() - 2])
-                                && '\n' == char::from(buf[buf.len() - 1]) {
-                                break;
-                            }
-                        }
-                        Err(e) => {
-                            return Err(anyhow!(e));
-                        }
-                    }
-                }
-
-                return Ok(HeosReply::parse(String::from_utf8(buf)?.as_str())?)
-            }
-            _ => {}
+        match self.connection.as_ref() {
+            Some(connection) => HeosReply::parse(connection.send(&dev_cmd).await?.as_str()),
+            None => Err(HeosError::ConnectionClosed),
         }
-
-        Err(anyhow!("Failed to send command"))
     }
 }
 
@@ -183,8 +946,13 @@ impl Clone for HeosDevice {
             player_id: self.player_id.clone(),
             group_id: self.group_id.clone(),
             volume: self.volume,
+            muted: self.muted,
+            repeat: self.repeat,
+            shuffle: self.shuffle,
             media: self.media.clone(),
-            stream: None,
+            progress: self.progress.clone(),
+            connection: self.connection.clone(),
+            timeout: self.timeout,
         }
     }
 }
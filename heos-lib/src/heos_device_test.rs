@@ -11,11 +11,20 @@
 
 #[cfg(test)]
 mod heos_device_test {
+    use crate::heos_browse::{AddCriteria, HeosBrowseItem, ServiceOption, ServiceOptionTarget};
     use crate::heos_command::{HeosCommand, HeosCommandHandler};
+    use crate::heos_error::{HeosError, HeosErrorCode};
+    use crate::heos_media::{HeosMediaSourceType, HeosMediaType};
+    use crate::heos_mock::{as_line, mock_connection};
     use crate::heos_reply::HeosReply;
-    use crate::HeosDevice;
+    use crate::heos_state::{AccountState, HeosInput, PlayState, RepeatMode, ShuffleMode};
+    use crate::{test_asset, HeosDevice};
+    use futures_util::{pin_mut, StreamExt};
     use pretty_assertions::assert_eq;
     use rstest::{fixture, rstest};
+    use std::ops::RangeInclusive;
+    use std::time::Duration;
+    use tokio::sync::mpsc::UnboundedReceiver;
 
     #[fixture]
     fn heos_device() -> HeosDevice {
@@ -24,6 +33,18 @@ mod heos_device_test {
             .expect("Failed to create device")
     }
 
+    async fn mock_heos_device(response: &str) -> (HeosDevice, UnboundedReceiver<String>) {
+        let (connection, commands) = mock_connection(1, vec![as_line(response)]).await;
+
+        let mut heos_device = HeosDevice::new(env!("TEST_DEVICE_NAME"),
+                                              env!("TEST_DEVICE_IP"), "844263156")
+            .expect("Failed to create device");
+
+        heos_device.connection = Some(connection);
+
+        (heos_device, commands)
+    }
+
     #[rstest]
     fn should_clone_and_compare_with_itself(heos_device: HeosDevice) {
         let cloned = heos_device.clone();
@@ -31,6 +52,552 @@ mod heos_device_test {
         assert_eq!(heos_device, cloned);
     }
 
+    #[tokio::test]
+    async fn should_get_play_state() {
+        let (mut heos_device, mut commands) = mock_heos_device(
+            test_asset!("player_get_play_state.json")).await;
+
+        let state = heos_device.get_play_state().await
+            .expect("Failed to get play state");
+
+        assert_eq!(state, PlayState::Play);
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   "heos://player/get_play_state?pid=844263156");
+    }
+
+    #[tokio::test]
+    async fn should_update_media() {
+        let (mut heos_device, mut commands) = mock_heos_device(
+            test_asset!("get_now_playing_song.json")).await;
+
+        heos_device.update_media().await
+            .expect("Failed to update media");
+
+        let media = heos_device.media.expect("Failed to get media");
+
+        assert_eq!(media.media_type, HeosMediaType::Song);
+        assert_eq!(media.source_type, HeosMediaSourceType::LocalMedia);
+        assert_eq!(media.song_title, "Heroes");
+        assert_eq!(media.album_title, "Heroes");
+        assert_eq!(media.artist_title, "David Bowie");
+        assert_eq!(media.media_id, "22$3$4711");
+        assert_eq!(media.album_id, "21$12");
+        assert_eq!(media.queue_id, Some(3));
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   "heos://player/get_now_playing_media?pid=844263156");
+    }
+
+    #[tokio::test]
+    async fn should_clear_media_when_nothing_plays() {
+        let (mut heos_device, _) = mock_heos_device(
+            test_asset!("get_now_playing_nothing.json")).await;
+
+        heos_device.update_media().await
+            .expect("Failed to update media");
+
+        assert_eq!(heos_device.media, None);
+    }
+
+    #[tokio::test]
+    async fn should_get_service_options() {
+        let (mut heos_device, mut commands) = mock_heos_device(
+            test_asset!("get_service_options.json")).await;
+
+        let options = heos_device.get_service_options("1").await
+            .expect("Failed to get service options");
+
+        assert_eq!(options.len(), 3);
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   "heos://browse/get_service_options?sid=1");
+    }
+
+    #[tokio::test]
+    async fn should_set_now_playing_service_option() {
+        let (mut heos_device, mut commands) = mock_heos_device(
+            test_asset!("set_service_option.json")).await;
+
+        heos_device.media = HeosReply::parse_now_playing(
+            &gjson::parse(test_asset!("get_now_playing_station.json")));
+
+        heos_device.set_service_option(ServiceOption::ThumbsUp, ServiceOptionTarget::NowPlaying).await
+            .expect("Failed to set service option");
+
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   "heos://browse/set_service_option?sid=3&option=11&pid=844263156");
+    }
+
+    #[tokio::test]
+    async fn should_add_now_playing_to_favorites() {
+        let (mut heos_device, mut commands) = mock_heos_device(
+            test_asset!("set_service_option_favorites.json")).await;
+
+        heos_device.media = HeosReply::parse_now_playing(
+            &gjson::parse(test_asset!("get_now_playing_station.json")));
+
+        heos_device.set_service_option(ServiceOption::AddToFavorites, ServiceOptionTarget::NowPlaying).await
+            .expect("Failed to set service option");
+
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   "heos://browse/set_service_option?option=19&pid=844263156");
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn should_reject_service_option_without_media(mut heos_device: HeosDevice) {
+        assert!(matches!(heos_device.set_service_option(ServiceOption::ThumbsUp,
+                                                        ServiceOptionTarget::NowPlaying).await,
+                         Err(HeosError::InvalidArgument(_))));
+    }
+
+    #[tokio::test]
+    async fn should_pause_progress_on_play_state() {
+        let (mut heos_device, _) = mock_heos_device(
+            test_asset!("set_play_state.json")).await;
+
+        heos_device.update_progress(1000, 180000);
+
+        heos_device.set_play_state(PlayState::Pause).await
+            .expect("Failed to set play state");
+
+        let progress = heos_device.progress.as_ref()
+            .expect("Failed to get progress");
+
+        assert_eq!(progress.state, PlayState::Pause);
+    }
+
+    #[rstest]
+    fn should_interpolate_progress(mut heos_device: HeosDevice) {
+        heos_device.update_progress(1000, 3000);
+
+        let progress = heos_device.progress.as_mut()
+            .expect("Failed to get progress");
+
+        let later = progress.updated_at + Duration::from_millis(1500);
+
+        assert_eq!(progress.position_at(later), Duration::from_millis(2500));
+        assert_eq!(progress.position_at(later + Duration::from_secs(10)), Duration::from_millis(3000));
+
+        progress.state = PlayState::Pause;
+
+        assert_eq!(progress.position_at(later), Duration::from_millis(1000));
+    }
+
+    #[tokio::test]
+    async fn should_get_play_mode() {
+        let (mut heos_device, _) = mock_heos_device(
+            test_asset!("get_play_mode.json")).await;
+
+        let mode = heos_device.get_play_mode().await
+            .expect("Failed to get play mode");
+
+        assert_eq!(mode, (RepeatMode::OnAll, ShuffleMode::Off));
+        assert_eq!(heos_device.repeat, RepeatMode::OnAll);
+    }
+
+    #[tokio::test]
+    async fn should_set_play_mode() {
+        let (mut heos_device, mut commands) = mock_heos_device(
+            test_asset!("set_play_mode.json")).await;
+
+        heos_device.set_play_mode(RepeatMode::OnOne, ShuffleMode::On).await
+            .expect("Failed to set play mode");
+
+        assert_eq!(heos_device.repeat, RepeatMode::OnOne);
+        assert_eq!(heos_device.shuffle, ShuffleMode::On);
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   "heos://player/set_play_mode?repeat=on_one&shuffle=on&pid=844263156");
+    }
+
+    #[tokio::test]
+    async fn should_set_volume() {
+        let (mut heos_device, mut commands) = mock_heos_device(
+            test_asset!("set_volume.json")).await;
+
+        heos_device.set_volume(30).await
+            .expect("Failed to set volume");
+
+        assert_eq!(heos_device.volume, 30);
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   "heos://player/set_volume?level=30&pid=844263156");
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn should_reject_invalid_volume(mut heos_device: HeosDevice) {
+        assert!(matches!(heos_device.set_volume(101).await,
+            Err(HeosError::InvalidArgument(_))));
+    }
+
+    #[tokio::test]
+    async fn should_step_volume_up() {
+        let (mut heos_device, mut commands) = mock_heos_device(
+            test_asset!("volume_up.json")).await;
+
+        heos_device.volume = 98;
+
+        heos_device.volume_up(5).await
+            .expect("Failed to raise volume");
+
+        /* Level is only known once the device reports it */
+        assert_eq!(heos_device.volume, 98);
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   "heos://player/volume_up?step=5&pid=844263156");
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn should_reject_invalid_volume_step(mut heos_device: HeosDevice) {
+        assert!(matches!(heos_device.volume_down(0).await,
+            Err(HeosError::InvalidArgument(_))));
+        assert!(matches!(heos_device.volume_down(11).await,
+            Err(HeosError::InvalidArgument(_))));
+    }
+
+    #[tokio::test]
+    async fn should_get_mute() {
+        let (mut heos_device, _) = mock_heos_device(
+            test_asset!("player_get_mute.json")).await;
+
+        heos_device.muted = true;
+
+        let mute = heos_device.get_mute().await
+            .expect("Failed to get mute");
+
+        assert!(!mute);
+        assert!(!heos_device.muted);
+    }
+
+    #[tokio::test]
+    async fn should_fail_to_update_volume_without_level() {
+        let (mut heos_device, _) = mock_heos_device(
+            test_asset!("get_volume_without_level.json")).await;
+
+        assert!(matches!(heos_device.update_volume().await, Err(HeosError::Parse(_))));
+    }
+
+    #[tokio::test]
+    async fn should_toggle_mute_state() {
+        let (mut heos_device, _) = mock_heos_device(
+            test_asset!("toggle_mute.json")).await;
+
+        heos_device.toggle_mute().await
+            .expect("Failed to toggle mute");
+
+        assert!(heos_device.muted);
+    }
+
+    #[tokio::test]
+    async fn should_fail_on_error_reply() {
+        let (mut heos_device, _) = mock_heos_device(
+            test_asset!("toggle_mute_error.json")).await;
+
+        let error = heos_device.toggle_mute().await
+            .expect_err("Expected error reply");
+
+        assert_eq!(error.code(), Some(HeosErrorCode::InvalidId));
+    }
+
+    #[tokio::test]
+    async fn should_check_account() {
+        let (mut heos_device, mut commands) = mock_heos_device(
+            test_asset!("check_account.json")).await;
+
+        let state = heos_device.check_account().await
+            .expect("Failed to check account");
+
+        assert_eq!(state, AccountState::SignedIn { user_name: "heos@example.com".into() });
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   "heos://system/check_account");
+    }
+
+    #[tokio::test]
+    async fn should_sign_in_with_encoded_credentials() {
+        let (mut heos_device, mut commands) = mock_heos_device(
+            test_asset!("sign_in.json")).await;
+
+        let state = heos_device.sign_in("heos@example.com", "p&ss=100%").await
+            .expect("Failed to sign in");
+
+        assert!(state.is_signed_in());
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   "heos://system/sign_in?un=heos@example.com&pw=p%26ss%3D100%25");
+    }
+
+    #[tokio::test]
+    async fn should_fail_to_sign_in() {
+        let (mut heos_device, _) = mock_heos_device(
+            test_asset!("sign_in_failed.json")).await;
+
+        let error = heos_device.sign_in("heos@example.com", "wrong").await
+            .expect_err("Expected error reply");
+
+        assert_eq!(error.code(), Some(HeosErrorCode::UserNotFound));
+    }
+
+    #[tokio::test]
+    async fn should_sign_out() {
+        let (mut heos_device, mut commands) = mock_heos_device(
+            test_asset!("sign_out.json")).await;
+
+        let state = heos_device.sign_out().await
+            .expect("Failed to sign out");
+
+        assert_eq!(state, AccountState::SignedOut);
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   "heos://system/sign_out");
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn should_reject_empty_credentials(mut heos_device: HeosDevice) {
+        assert!(matches!(heos_device.sign_in("heos@example.com", "").await,
+                         Err(HeosError::InvalidArgument(_))));
+    }
+
+    #[tokio::test]
+    async fn should_get_queue_range() {
+        let (mut heos_device, mut commands) = mock_heos_device(
+            test_asset!("get_queue.json")).await;
+
+        let items = heos_device.get_queue(0..=2).await
+            .expect("Failed to get queue");
+
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].queue_id, 1);
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   "heos://player/get_queue?range=0,2&pid=844263156");
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn should_reject_invalid_queue_range(mut heos_device: HeosDevice) {
+        assert!(matches!(heos_device.get_queue(0..=100).await,
+            Err(HeosError::InvalidArgument(_))));
+        assert!(matches!(heos_device.get_queue(RangeInclusive::new(5, 4)).await,
+            Err(HeosError::InvalidArgument(_))));
+    }
+
+    #[tokio::test]
+    async fn should_remove_from_queue() {
+        let (mut heos_device, mut commands) = mock_heos_device(
+            test_asset!("remove_from_queue.json")).await;
+
+        heos_device.remove_from_queue(&[2, 3]).await
+            .expect("Failed to remove from queue");
+
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   "heos://player/remove_from_queue?qid=2,3&pid=844263156");
+    }
+
+    #[tokio::test]
+    async fn should_move_queue_item() {
+        let (mut heos_device, mut commands) = mock_heos_device(
+            test_asset!("move_queue_item.json")).await;
+
+        heos_device.move_queue_item(&[2, 3], 1).await
+            .expect("Failed to move queue items");
+
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   "heos://player/move_queue_item?sqid=2,3&dqid=1&pid=844263156");
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn should_reject_invalid_queue_arguments(mut heos_device: HeosDevice) {
+        assert!(matches!(heos_device.remove_from_queue(&[]).await,
+            Err(HeosError::InvalidArgument(_))));
+        assert!(matches!(heos_device.save_queue("").await,
+            Err(HeosError::InvalidArgument(_))));
+        assert!(matches!(heos_device.save_queue(&"x".repeat(129)).await,
+            Err(HeosError::InvalidArgument(_))));
+    }
+
+    #[tokio::test]
+    async fn should_form_group() {
+        let (mut heos_device, mut commands) = mock_heos_device(
+            test_asset!("set_group.json")).await;
+
+        let group = heos_device.set_group(&["-1441236068"]).await
+            .expect("Failed to set group")
+            .expect("Expected new group");
+
+        assert_eq!(group.group_id, "-1859434560");
+        assert_eq!(group.leader, Some(heos_device.clone()));
+        assert_eq!(heos_device.group_id, "-1859434560");
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   "heos://group/set_group?pid=844263156,-1441236068");
+    }
+
+    #[tokio::test]
+    async fn should_reject_group_without_members() {
+        let (mut heos_device, mut commands) = mock_heos_device(
+            test_asset!("set_group_ungroup.json")).await;
+
+        assert!(matches!(heos_device.set_group(&[]).await,
+            Err(HeosError::InvalidArgument(_))));
+        assert!(commands.try_recv().is_err());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn should_reject_leader_as_member(mut heos_device: HeosDevice) {
+        heos_device.player_id = "844263156".into();
+
+        assert!(matches!(heos_device.set_group(&["844263156"]).await,
+            Err(HeosError::InvalidArgument(_))));
+    }
+
+    #[tokio::test]
+    async fn should_get_music_sources() {
+        let (mut heos_device, mut commands) = mock_heos_device(
+            test_asset!("get_music_sources.json")).await;
+
+        let sources = heos_device.get_music_sources().await
+            .expect("Failed to get music sources");
+
+        assert_eq!(sources.len(), 4);
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   "heos://browse/get_music_sources");
+    }
+
+    #[tokio::test]
+    async fn should_browse_container_range() {
+        let (mut heos_device, mut commands) = mock_heos_device(
+            test_asset!("browse_container.json")).await;
+
+        let result = heos_device.browse("1513473564", Some("21$12"), Some(0..=2)).await
+            .expect("Failed to browse container");
+
+        assert_eq!(result.container_id.as_deref(), Some("21$12"));
+        assert_eq!(result.returned, 3);
+        assert_eq!(result.count, 8);
+        assert_eq!(result.items.len(), 3);
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   "heos://browse/browse?sid=1513473564&cid=21$12&range=0,2");
+    }
+
+    #[tokio::test]
+    async fn should_add_track_to_queue() {
+        let (mut heos_device, mut commands) = mock_heos_device(
+            test_asset!("add_to_queue.json")).await;
+
+        heos_device.add_to_queue("1513473564", "21$12", Some("22$3$4711"), AddCriteria::AddToEnd).await
+            .expect("Failed to add to queue");
+
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   "heos://browse/add_to_queue?pid=844263156&sid=1513473564&cid=21$12&mid=22$3$4711&aid=3");
+    }
+
+    #[tokio::test]
+    async fn should_search_source() {
+        let (mut heos_device, mut commands) = mock_heos_device(
+            test_asset!("search.json")).await;
+
+        let result = heos_device.search("1513473564", "3", "Heroes", Some(0..=1)).await
+            .expect("Failed to search");
+
+        assert_eq!(result.returned, 2);
+        assert_eq!(result.count, 5);
+        assert!(matches!(&result.items[1], HeosBrowseItem::Media(media)
+            if "Motörhead" == media.artist_title));
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   "heos://browse/search?sid=1513473564&search=Heroes&scid=3&range=0,1");
+    }
+
+    #[tokio::test]
+    async fn should_search_multiple_sources() {
+        let (mut heos_device, mut commands) = mock_heos_device(
+            test_asset!("multi_search.json")).await;
+
+        let result = heos_device.multi_search("Bowie", &["3", "1513473564"], &["1"]).await
+            .expect("Failed to search");
+
+        assert_eq!(result.source_id, "3,1513473564");
+        assert_eq!(result.count, 1);
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   "heos://browse/multi_search?search=Bowie&sid=3,1513473564&scid=1");
+    }
+
+    #[tokio::test]
+    async fn should_get_favorites() {
+        let (mut heos_device, mut commands) = mock_heos_device(
+            test_asset!("browse_favorites.json")).await;
+
+        let favorites = heos_device.get_favorites().await
+            .expect("Failed to get favorites");
+
+        assert_eq!(favorites.len(), 3);
+        assert_eq!(favorites[2].name, "Deutschlandfunk");
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   "heos://browse/browse?sid=1028");
+    }
+
+    #[tokio::test]
+    async fn should_play_preset() {
+        let (mut heos_device, mut commands) = mock_heos_device(
+            test_asset!("play_preset.json")).await;
+
+        heos_device.play_preset(3).await
+            .expect("Failed to play preset");
+
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   "heos://browse/play_preset?pid=844263156&preset=3");
+    }
+
+    #[tokio::test]
+    async fn should_play_stream() {
+        let (mut heos_device, mut commands) = mock_heos_device(
+            test_asset!("play_stream.json")).await;
+
+        heos_device.play_stream("http://streams.radiobob.de/bob-live/mp3-192/mediaplayer").await
+            .expect("Failed to play stream");
+
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   "heos://browse/play_stream?pid=844263156&url=http://streams.radiobob.de/bob-live/mp3-192/mediaplayer");
+    }
+
+    #[tokio::test]
+    async fn should_play_input_of_other_player() {
+        let (mut heos_device, mut commands) = mock_heos_device(
+            test_asset!("play_input.json")).await;
+
+        heos_device.play_input(HeosInput::HdmiArc1, Some("-474905601")).await
+            .expect("Failed to play input");
+
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   "heos://browse/play_input?pid=844263156&spid=-474905601&input=inputs/hdmi_arc_1");
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn should_reject_invalid_stream_url(mut heos_device: HeosDevice) {
+        assert!(matches!(heos_device.play_stream("file:///etc/passwd").await,
+            Err(HeosError::InvalidArgument(_))));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn should_reject_invalid_preset(mut heos_device: HeosDevice) {
+        assert!(matches!(heos_device.play_preset(0).await,
+            Err(HeosError::InvalidArgument(_))));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn should_reject_invalid_search(mut heos_device: HeosDevice) {
+        assert!(matches!(heos_device.search("1024", "1", "", None).await,
+            Err(HeosError::InvalidArgument(_))));
+        assert!(matches!(heos_device.multi_search(&"x".repeat(129), &[], &[]).await,
+            Err(HeosError::InvalidArgument(_))));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn should_reject_invalid_browse_range(mut heos_device: HeosDevice) {
+        assert!(matches!(heos_device.browse("1024", None, Some(0..=100)).await,
+            Err(HeosError::InvalidArgument(_))));
+    }
+
     #[ignore]
     #[rstest]
     #[tokio::test]
@@ -88,9 +655,11 @@ mod heos_device_test {
 
         assert!(matches!(reply, HeosReply::PlayingMedia { .. }));
 
-        if let HeosReply::PlayingMedia(_success, payload) = reply {
-            assert!(payload.get("artist").is_some());
-            assert!(payload.get("song").is_some());
+        if let HeosReply::PlayingMedia(_success, media) = reply {
+            let media = media.expect("Failed to get media");
+
+            assert!(!media.artist_title.is_empty());
+            assert!(!media.song_title.is_empty());
         }
     }
 
@@ -116,4 +685,18 @@ mod heos_device_test {
             assert!(payload.get("level").is_some());
         }
     }
+
+    #[ignore]
+    #[rstest]
+    #[tokio::test]
+    async fn should_register_for_change_events(mut heos_device: HeosDevice) {
+        let events = heos_device.events().await
+            .expect("Failed to register for change events");
+        pin_mut!(events);
+
+        match events.next().await {
+            Some(event) => println!("{:?}", event),
+            None => panic!("Failed to receive events"),
+        }
+    }
 }
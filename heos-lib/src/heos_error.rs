@@ -0,0 +1,223 @@
+///
+/// @package heos-dial
+///
+/// @file HEOS lib
+/// @copyright (c) 2024-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv3.
+/// See the file LICENSE for details.
+///
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt::{Display, Formatter};
+use std::num::ParseIntError;
+use std::str::Utf8Error;
+use std::string::FromUtf8Error;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, HeosError>;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum HeosErrorCode {
+    UnrecognizedCommand,
+    InvalidId,
+    WrongNumberOfArguments,
+    DataNotAvailable,
+    ResourceNotAvailable,
+    InvalidCredentials,
+    CommandNotExecuted,
+    UserNotLoggedIn,
+    ParameterOutOfRange,
+    UserNotFound,
+    InternalError,
+    SystemError,
+    ProcessingPreviousCommand,
+    MediaCannotBePlayed,
+    OptionNotSupported,
+    TooManyCommands,
+    SkipLimitReached,
+    Unknown(i32),
+}
+
+impl HeosErrorCode {
+    pub fn from_eid(eid: i32) -> Self {
+        match eid {
+            1 => HeosErrorCode::UnrecognizedCommand,
+            2 => HeosErrorCode::InvalidId,
+            3 => HeosErrorCode::WrongNumberOfArguments,
+            4 => HeosErrorCode::DataNotAvailable,
+            5 => HeosErrorCode::ResourceNotAvailable,
+            6 => HeosErrorCode::InvalidCredentials,
+            7 => HeosErrorCode::CommandNotExecuted,
+            8 => HeosErrorCode::UserNotLoggedIn,
+            9 => HeosErrorCode::ParameterOutOfRange,
+            10 => HeosErrorCode::UserNotFound,
+            11 => HeosErrorCode::InternalError,
+            12 => HeosErrorCode::SystemError,
+            13 => HeosErrorCode::ProcessingPreviousCommand,
+            14 => HeosErrorCode::MediaCannotBePlayed,
+            15 => HeosErrorCode::OptionNotSupported,
+            16 => HeosErrorCode::TooManyCommands,
+            17 => HeosErrorCode::SkipLimitReached,
+            eid => HeosErrorCode::Unknown(eid),
+        }
+    }
+
+    pub fn eid(&self) -> i32 {
+        match self {
+            HeosErrorCode::UnrecognizedCommand => 1,
+            HeosErrorCode::InvalidId => 2,
+            HeosErrorCode::WrongNumberOfArguments => 3,
+            HeosErrorCode::DataNotAvailable => 4,
+            HeosErrorCode::ResourceNotAvailable => 5,
+            HeosErrorCode::InvalidCredentials => 6,
+            HeosErrorCode::CommandNotExecuted => 7,
+            HeosErrorCode::UserNotLoggedIn => 8,
+            HeosErrorCode::ParameterOutOfRange => 9,
+            HeosErrorCode::UserNotFound => 10,
+            HeosErrorCode::InternalError => 11,
+            HeosErrorCode::SystemError => 12,
+            HeosErrorCode::ProcessingPreviousCommand => 13,
+            HeosErrorCode::MediaCannotBePlayed => 14,
+            HeosErrorCode::OptionNotSupported => 15,
+            HeosErrorCode::TooManyCommands => 16,
+            HeosErrorCode::SkipLimitReached => 17,
+            HeosErrorCode::Unknown(eid) => *eid,
+        }
+    }
+}
+
+impl Display for HeosErrorCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            HeosErrorCode::UnrecognizedCommand => "Unrecognized command",
+            HeosErrorCode::InvalidId => "Invalid ID",
+            HeosErrorCode::WrongNumberOfArguments => "Wrong number of command arguments",
+            HeosErrorCode::DataNotAvailable => "Requested data not available",
+            HeosErrorCode::ResourceNotAvailable => "Resource currently not available",
+            HeosErrorCode::InvalidCredentials => "Invalid credentials",
+            HeosErrorCode::CommandNotExecuted => "Command could not be executed",
+            HeosErrorCode::UserNotLoggedIn => "User not logged in",
+            HeosErrorCode::ParameterOutOfRange => "Parameter out of range",
+            HeosErrorCode::UserNotFound => "User not found",
+            HeosErrorCode::InternalError => "Internal error",
+            HeosErrorCode::SystemError => "System error",
+            HeosErrorCode::ProcessingPreviousCommand => "Processing previous command",
+            HeosErrorCode::MediaCannotBePlayed => "Media can't be played",
+            HeosErrorCode::OptionNotSupported => "Option not supported",
+            HeosErrorCode::TooManyCommands => "Too many commands in message queue to process",
+            HeosErrorCode::SkipLimitReached => "Reached skip limit",
+            HeosErrorCode::Unknown(_) => "Unknown error",
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum HeosSystemError {
+    RemoteServiceError,
+    ServiceNotRegistered,
+    UserNotLoggedIn,
+    UserNotFound,
+    AuthenticationError,
+    AuthorizationError,
+    UserParametersInvalid,
+    Unknown(i32),
+}
+
+impl HeosSystemError {
+    pub fn from_syserrno(syserrno: i32) -> Self {
+        match syserrno {
+            -9 => HeosSystemError::RemoteServiceError,
+            -1061 => HeosSystemError::ServiceNotRegistered,
+            -1063 => HeosSystemError::UserNotLoggedIn,
+            -1201 => HeosSystemError::UserNotFound,
+            -1232 => HeosSystemError::AuthenticationError,
+            -1239 => HeosSystemError::AuthorizationError,
+            -1357 => HeosSystemError::UserParametersInvalid,
+            syserrno => HeosSystemError::Unknown(syserrno),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum HeosError {
+    #[error("Command `{command}` failed: {code} ({text})")]
+    Command {
+        command: String,
+        code: HeosErrorCode,
+        syserrno: Option<HeosSystemError>,
+        text: String,
+    },
+
+    #[error("Command `{0}` unknown")]
+    UnknownCommand(String),
+
+    #[error("Unexpected reply: {0}")]
+    UnexpectedReply(String),
+
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+
+    #[error("Parse error: {0}")]
+    Parse(String),
+
+    #[error("Command `{0}` timed out")]
+    Timeout(String),
+
+    #[error("Connection closed")]
+    ConnectionClosed,
+
+    #[error("No leader found")]
+    NoLeader,
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+impl HeosError {
+    pub(crate) fn from_message(command: &str, message: &HashMap<String, String>) -> Self {
+        HeosError::Command {
+            command: command.into(),
+            code: HeosErrorCode::from_eid(message.get("eid")
+                .and_then(|eid| eid.parse::<i32>().ok())
+                .unwrap_or_default()),
+            syserrno: message.get("syserrno")
+                .and_then(|syserrno| syserrno.parse::<i32>().ok())
+                .map(HeosSystemError::from_syserrno),
+            text: message.get("text").cloned().unwrap_or_default(),
+        }
+    }
+
+    pub fn code(&self) -> Option<HeosErrorCode> {
+        match self {
+            HeosError::Command { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+}
+
+impl From<ParseIntError> for HeosError {
+    fn from(err: ParseIntError) -> Self {
+        HeosError::Parse(err.to_string())
+    }
+}
+
+impl From<Utf8Error> for HeosError {
+    fn from(err: Utf8Error) -> Self {
+        HeosError::Parse(err.to_string())
+    }
+}
+
+impl From<FromUtf8Error> for HeosError {
+    fn from(err: FromUtf8Error) -> Self {
+        HeosError::Parse(err.to_string())
+    }
+}
+
+impl From<Infallible> for HeosError {
+    fn from(err: Infallible) -> Self {
+        match err {}
+    }
+}
\ No newline at end of file
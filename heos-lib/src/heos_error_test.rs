@@ -0,0 +1,71 @@
+///
+/// @package heos-dial
+///
+/// @file HEOS error tests
+/// @copyright (c) 2024-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv3.
+/// See the file LICENSE for details.
+///
+
+#[cfg(test)]
+mod heos_error_test {
+    use crate::heos_error::{HeosError, HeosErrorCode, HeosSystemError};
+    use crate::heos_reply::HeosReply;
+    use pretty_assertions::assert_eq;
+    use crate::test_asset;
+
+    #[test]
+    fn should_map_error_codes() {
+        for eid in 1..=17 {
+            assert_eq!(HeosErrorCode::from_eid(eid).eid(), eid);
+            assert!(!matches!(HeosErrorCode::from_eid(eid), HeosErrorCode::Unknown(_)));
+        }
+
+        assert_eq!(HeosErrorCode::from_eid(42), HeosErrorCode::Unknown(42));
+    }
+
+    #[test]
+    fn should_map_system_error_codes() {
+        assert_eq!(HeosSystemError::from_syserrno(-9), HeosSystemError::RemoteServiceError);
+        assert_eq!(HeosSystemError::from_syserrno(-1232), HeosSystemError::AuthenticationError);
+        assert_eq!(HeosSystemError::from_syserrno(-1), HeosSystemError::Unknown(-1));
+    }
+
+    #[test]
+    fn should_convert_error_reply() {
+        let reply = HeosReply::parse(test_asset!("toggle_mute_error.json"))
+            .expect("Failed to parse toggle_mute_error.json");
+
+        if let HeosError::Command { command, code, syserrno, text } = reply.into_error() {
+            assert_eq!(command, "player/toggle_mute");
+            assert_eq!(code, HeosErrorCode::InvalidId);
+            assert_eq!(syserrno, None);
+            assert_eq!(text, "ID Not Valid");
+        } else {
+            panic!("Wrong error type");
+        }
+    }
+
+    #[test]
+    fn should_convert_system_error_reply() {
+        let error = HeosReply::parse(test_asset!("system_error.json"))
+            .expect("Failed to parse system_error.json")
+            .into_error();
+
+        assert_eq!(error.code(), Some(HeosErrorCode::SystemError));
+        assert!(matches!(error, HeosError::Command {
+            syserrno: Some(HeosSystemError::UserNotLoggedIn), .. }));
+    }
+
+    #[test]
+    fn should_convert_unexpected_reply() {
+        let error = HeosReply::parse(test_asset!("get_players.json"))
+            .expect("Failed to parse get_players.json")
+            .into_error();
+
+        assert!(matches!(error, HeosError::UnexpectedReply(_)));
+        assert_eq!(error.code(), None);
+    }
+}
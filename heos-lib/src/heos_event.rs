@@ -0,0 +1,133 @@
+///
+/// @package heos-dial
+///
+/// @file HEOS lib
+/// @copyright (c) 2024-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv3.
+/// See the file LICENSE for details.
+///
+
+use crate::heos_error::{HeosError, Result};
+use crate::heos_reply::HeosReply;
+use crate::heos_state::{parse_on_off, PlayState, RepeatMode, ShuffleMode};
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum HeosEvent {
+    SourcesChanged,
+    PlayersChanged,
+    GroupsChanged,
+    PlayerStateChanged {
+        player_id: String,
+        state: PlayState,
+    },
+    PlayerNowPlayingChanged {
+        player_id: String,
+    },
+    PlayerNowPlayingProgress {
+        player_id: String,
+        cur_pos: u64,
+        duration: u64,
+    },
+    PlayerPlaybackError {
+        player_id: String,
+        error: String,
+    },
+    PlayerQueueChanged {
+        player_id: String,
+    },
+    PlayerVolumeChanged {
+        player_id: String,
+        level: u16,
+        mute: bool,
+    },
+    RepeatModeChanged {
+        player_id: String,
+        repeat: RepeatMode,
+    },
+    ShuffleModeChanged {
+        player_id: String,
+        shuffle: ShuffleMode,
+    },
+    GroupVolumeChanged {
+        group_id: String,
+        level: u16,
+        mute: bool,
+    },
+    UserChanged {
+        signed_in: bool,
+        user_name: Option<String>,
+    },
+}
+
+impl HeosEvent {
+    pub fn parse(event_str: &str) -> Result<HeosEvent> {
+        let json = gjson::parse(event_str);
+        let attrs = HeosReply::parse_message(&json, "heos.message");
+
+        match json.get("heos.command").str() {
+            "event/sources_changed" => Ok(HeosEvent::SourcesChanged),
+            "event/players_changed" => Ok(HeosEvent::PlayersChanged),
+            "event/groups_changed" => Ok(HeosEvent::GroupsChanged),
+
+            "event/player_state_changed" => Ok(HeosEvent::PlayerStateChanged {
+                player_id: HeosReply::get_attr(&attrs, "pid")?.into(),
+                state: HeosReply::get_attr(&attrs, "state")?.parse::<PlayState>()?,
+            }),
+
+            "event/player_now_playing_changed" => Ok(HeosEvent::PlayerNowPlayingChanged {
+                player_id: HeosReply::get_attr(&attrs, "pid")?.into(),
+            }),
+
+            "event/player_now_playing_progress" => Ok(HeosEvent::PlayerNowPlayingProgress {
+                player_id: HeosReply::get_attr(&attrs, "pid")?.into(),
+                cur_pos: HeosReply::get_attr(&attrs, "cur_pos")?.parse::<u64>()?,
+                duration: HeosReply::get_attr(&attrs, "duration")?.parse::<u64>()?,
+            }),
+
+            "event/player_playback_error" => Ok(HeosEvent::PlayerPlaybackError {
+                player_id: HeosReply::get_attr(&attrs, "pid")?.into(),
+                error: HeosReply::get_attr(&attrs, "error")?.into(),
+            }),
+
+            "event/player_queue_changed" => Ok(HeosEvent::PlayerQueueChanged {
+                player_id: HeosReply::get_attr(&attrs, "pid")?.into(),
+            }),
+
+            "event/player_volume_changed" => Ok(HeosEvent::PlayerVolumeChanged {
+                player_id: HeosReply::get_attr(&attrs, "pid")?.into(),
+                level: HeosReply::get_attr(&attrs, "level")?.parse::<u16>()?,
+                mute: parse_on_off(HeosReply::get_attr(&attrs, "mute")?)?,
+            }),
+
+            "event/repeat_mode_changed" => Ok(HeosEvent::RepeatModeChanged {
+                player_id: HeosReply::get_attr(&attrs, "pid")?.into(),
+                repeat: HeosReply::get_attr(&attrs, "repeat")?.parse::<RepeatMode>()?,
+            }),
+
+            "event/shuffle_mode_changed" => Ok(HeosEvent::ShuffleModeChanged {
+                player_id: HeosReply::get_attr(&attrs, "pid")?.into(),
+                shuffle: HeosReply::get_attr(&attrs, "shuffle")?.parse::<ShuffleMode>()?,
+            }),
+
+            "event/group_volume_changed" => Ok(HeosEvent::GroupVolumeChanged {
+                group_id: HeosReply::get_attr(&attrs, "gid")?.into(),
+                level: HeosReply::get_attr(&attrs, "level")?.parse::<u16>()?,
+                mute: parse_on_off(HeosReply::get_attr(&attrs, "mute")?)?,
+            }),
+
+            /* Message is either `signed_out` or `signed_in&un=name` */
+            "event/user_changed" => Ok(HeosEvent::UserChanged {
+                signed_in: json.get("heos.message").str().starts_with("signed_in"),
+                user_name: attrs.get("un").cloned(),
+            }),
+
+            cmd => Err(HeosError::UnknownCommand(cmd.into())),
+        }
+    }
+
+    pub fn is_event(response_str: &str) -> bool {
+        gjson::get(response_str, "heos.command").str().starts_with("event/")
+    }
+}
\ No newline at end of file
@@ -0,0 +1,172 @@
+///
+/// @package heos-dial
+///
+/// @file HEOS event tests
+/// @copyright (c) 2024-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv3.
+/// See the file LICENSE for details.
+///
+
+#[cfg(test)]
+mod heos_event_test {
+    use crate::heos_event::HeosEvent;
+    use crate::heos_state::{PlayState, RepeatMode, ShuffleMode};
+    use pretty_assertions::assert_eq;
+    use crate::test_asset;
+
+    #[test]
+    fn should_identify_events() {
+        assert!(HeosEvent::is_event(test_asset!("event_players_changed.json")));
+        assert!(!HeosEvent::is_event(test_asset!("get_players.json")));
+    }
+
+    #[test]
+    fn should_parse_sources_changed_event() {
+        let event = HeosEvent::parse(test_asset!("event_sources_changed.json"))
+            .expect("Failed to parse event_sources_changed.json");
+
+        assert_eq!(event, HeosEvent::SourcesChanged);
+    }
+
+    #[test]
+    fn should_parse_players_changed_event() {
+        let event = HeosEvent::parse(test_asset!("event_players_changed.json"))
+            .expect("Failed to parse event_players_changed.json");
+
+        assert_eq!(event, HeosEvent::PlayersChanged);
+    }
+
+    #[test]
+    fn should_parse_groups_changed_event() {
+        let event = HeosEvent::parse(test_asset!("event_groups_changed.json"))
+            .expect("Failed to parse event_groups_changed.json");
+
+        assert_eq!(event, HeosEvent::GroupsChanged);
+    }
+
+    #[test]
+    fn should_parse_player_state_changed_event() {
+        let event = HeosEvent::parse(test_asset!("event_player_state_changed.json"))
+            .expect("Failed to parse event_player_state_changed.json");
+
+        assert_eq!(event, HeosEvent::PlayerStateChanged {
+            player_id: "844263156".into(),
+            state: PlayState::Play,
+        });
+    }
+
+    #[test]
+    fn should_parse_player_now_playing_changed_event() {
+        let event = HeosEvent::parse(test_asset!("event_player_now_playing_changed.json"))
+            .expect("Failed to parse event_player_now_playing_changed.json");
+
+        assert_eq!(event, HeosEvent::PlayerNowPlayingChanged {
+            player_id: "844263156".into(),
+        });
+    }
+
+    #[test]
+    fn should_parse_player_now_playing_progress_event() {
+        let event = HeosEvent::parse(test_asset!("event_player_now_playing_progress.json"))
+            .expect("Failed to parse event_player_now_playing_progress.json");
+
+        assert_eq!(event, HeosEvent::PlayerNowPlayingProgress {
+            player_id: "844263156".into(),
+            cur_pos: 62000,
+            duration: 215000,
+        });
+    }
+
+    #[test]
+    fn should_parse_player_playback_error_event() {
+        let event = HeosEvent::parse(test_asset!("event_player_playback_error.json"))
+            .expect("Failed to parse event_player_playback_error.json");
+
+        assert_eq!(event, HeosEvent::PlayerPlaybackError {
+            player_id: "844263156".into(),
+            error: "Could not Login".into(),
+        });
+    }
+
+    #[test]
+    fn should_parse_player_queue_changed_event() {
+        let event = HeosEvent::parse(test_asset!("event_player_queue_changed.json"))
+            .expect("Failed to parse event_player_queue_changed.json");
+
+        assert_eq!(event, HeosEvent::PlayerQueueChanged {
+            player_id: "844263156".into(),
+        });
+    }
+
+    #[test]
+    fn should_parse_player_volume_changed_event() {
+        let event = HeosEvent::parse(test_asset!("event_player_volume_changed.json"))
+            .expect("Failed to parse event_player_volume_changed.json");
+
+        assert_eq!(event, HeosEvent::PlayerVolumeChanged {
+            player_id: "844263156".into(),
+            level: 25,
+            mute: false,
+        });
+    }
+
+    #[test]
+    fn should_parse_repeat_mode_changed_event() {
+        let event = HeosEvent::parse(test_asset!("event_repeat_mode_changed.json"))
+            .expect("Failed to parse event_repeat_mode_changed.json");
+
+        assert_eq!(event, HeosEvent::RepeatModeChanged {
+            player_id: "844263156".into(),
+            repeat: RepeatMode::OnAll,
+        });
+    }
+
+    #[test]
+    fn should_parse_shuffle_mode_changed_event() {
+        let event = HeosEvent::parse(test_asset!("event_shuffle_mode_changed.json"))
+            .expect("Failed to parse event_shuffle_mode_changed.json");
+
+        assert_eq!(event, HeosEvent::ShuffleModeChanged {
+            player_id: "844263156".into(),
+            shuffle: ShuffleMode::On,
+        });
+    }
+
+    #[test]
+    fn should_parse_group_volume_changed_event() {
+        let event = HeosEvent::parse(test_asset!("event_group_volume_changed.json"))
+            .expect("Failed to parse event_group_volume_changed.json");
+
+        assert_eq!(event, HeosEvent::GroupVolumeChanged {
+            group_id: "-1859434560".into(),
+            level: 30,
+            mute: true,
+        });
+    }
+
+    #[test]
+    fn should_parse_user_changed_event() {
+        let mut event = HeosEvent::parse(test_asset!("event_user_changed.json"))
+            .expect("Failed to parse event_user_changed.json");
+
+        assert_eq!(event, HeosEvent::UserChanged {
+            signed_in: true,
+            user_name: Some("heos@example.com".into()),
+        });
+
+        event = HeosEvent::parse(test_asset!("event_user_changed_signed_out.json"))
+            .expect("Failed to parse event_user_changed_signed_out.json");
+
+        assert_eq!(event, HeosEvent::UserChanged {
+            signed_in: false,
+            user_name: None,
+        });
+    }
+
+    #[test]
+    fn should_fail_on_unknown_event() {
+        assert!(HeosEvent::parse(test_asset!("get_players.json")).is_err());
+    }
+}
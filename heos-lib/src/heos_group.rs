@@ -9,9 +9,10 @@
 /// See the file LICENSE for details.
 ///
 
-use crate::heos_command::{HeosCommand, HeosCommandHandler};
+use crate::heos_command::{check_range, HeosCommand, HeosCommandHandler};
+use crate::heos_error::{HeosError, Result};
+use crate::heos_state::{format_on_off, parse_on_off};
 use crate::{HeosDevice, HeosReply};
-use anyhow::{anyhow, Result};
 use std::fmt::{Display, Formatter};
 
 #[derive(Debug)]
@@ -21,6 +22,7 @@ pub struct HeosGroup {
     pub leader: Option<HeosDevice>,
     pub players: Option<Vec<HeosDevice>>,
     pub volume: u16,
+    pub muted: bool,
 }
 
 impl HeosGroup {
@@ -31,41 +33,243 @@ impl HeosGroup {
             leader: None,
             players: None,
             volume: 0,
+            muted: false,
         }
     }
 
-    pub async fn send_command<'a>(&mut self, cmd: &HeosCommand<'a>) -> Result<HeosReply> {
+    pub async fn play_preset(&mut self, preset: u8) -> Result<()> {
+        /* Presets are played on the leader and thereby the whole group */
+        match self.leader {
+            Some(ref mut leader) => leader.play_preset(preset).await,
+            None => Err(HeosError::NoLeader),
+        }
+    }
 
-        /* Append group id */
-        let group_cmd = cmd.clone().attr("gid", self.group_id.as_str());
+    pub async fn set_members(&mut self, member_ids: &[&str]) -> Result<()> {
+        if member_ids.is_empty() {
+            return Err(HeosError::InvalidArgument("Groups need at least one member".into()));
+        }
 
-        match self.leader {
-            Some(ref mut leader) => leader.send_command(&group_cmd).await,
-            None => Err(anyhow!("No leader found")),
+        let leader = self.leader.as_mut().ok_or(HeosError::NoLeader)?;
+
+        match leader.set_group(member_ids).await? {
+            Some(group) => {
+                self.name = group.name;
+                self.group_id = group.group_id;
+
+                Ok(())
+            },
+            None => Err(HeosError::UnexpectedReply(
+                format!("Group `{}` was dissolved", self.group_id))),
         }
     }
 
-    pub async fn update_volume(&mut self) -> Result<()> {
+    pub async fn add_members(&mut self, member_ids: &[&str]) -> Result<()> {
+        if member_ids.is_empty() {
+            return Err(HeosError::InvalidArgument("Nothing to add to the group".into()));
+        }
+
+        /* set_group replaces all members, so include the current ones */
+        if self.players.is_none() {
+            self.update_info().await?;
+        }
+
+        let leader_id = self.leader.as_ref()
+            .map(|leader| leader.player_id.clone())
+            .ok_or(HeosError::NoLeader)?;
+
+        let mut merged_ids: Vec<String> = Vec::new();
+
+        for player_id in self.players.iter().flatten()
+            .map(|player| player.player_id.as_str())
+            .chain(member_ids.iter().copied())
+        {
+            if player_id != leader_id && !merged_ids.iter().any(|id| id == player_id) {
+                merged_ids.push(player_id.to_string());
+            }
+        }
+
+        let merged_ids: Vec<&str> = merged_ids.iter().map(String::as_str).collect();
+
+        self.set_members(&merged_ids).await
+    }
+
+    pub async fn ungroup(&mut self) -> Result<()> {
         match self.leader {
-            Some(ref mut leader) => {
-                let cmd = HeosCommand::new()
-                    .group("group")
-                    .cmd("get_volume");
+            Some(ref mut leader) => leader.ungroup().await,
+            None => Err(HeosError::NoLeader),
+        }
+    }
 
-                let reply = leader.send_command(&cmd).await?;
+    pub async fn update_info(&mut self) -> Result<()> {
+        let cmd = HeosCommand::new()
+            .group("group")
+            .cmd("get_group_info");
 
-                if let HeosReply::Volume(success, attrs) = reply {
-                    if success {
-                        self.volume = attrs.get("level").unwrap().parse::<u16>()?;
+        match self.send_command(&cmd).await? {
+            HeosReply::GroupInfo(true, group) => {
+                self.name = group.name;
+                self.players = group.players;
+
+                if let (Some(leader), Some(new_leader)) = (self.leader.as_mut(), group.leader) {
+                    if *leader != new_leader {
+                        /* Group info usually lacks addresses, any device relays commands though,
+                           so only the id changes and the old connection stays in use */
+                        match new_leader.base_url.is_empty() {
+                            true => {
+                                leader.name = new_leader.name;
+                                leader.player_id = new_leader.player_id;
+                            },
+                            false => *leader = new_leader,
+                        }
                     }
-                } else if let HeosReply::Error(_, _, message) = reply {
-                    return Err(anyhow!(message.get("text")
-                        .expect("Expected error text to be set").to_string()));
                 }
 
                 Ok(())
             },
-            None => Err(anyhow!("No leader found")),
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn update_mute(&mut self) -> Result<()> {
+        self.get_mute().await?;
+
+        Ok(())
+    }
+
+    pub async fn update_volume(&mut self) -> Result<()> {
+        self.get_volume().await?;
+
+        Ok(())
+    }
+
+    pub async fn get_volume(&mut self) -> Result<u8> {
+        let cmd = HeosCommand::new()
+            .group("group")
+            .cmd("get_volume");
+
+        match self.send_command(&cmd).await? {
+            HeosReply::Volume(true, attrs) => {
+                let level = HeosReply::get_attr(&attrs, "level")?.parse::<u8>()?;
+
+                self.volume = level.into();
+
+                Ok(level)
+            },
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn set_volume(&mut self, level: u8) -> Result<()> {
+        check_range("level", level, 0..=100)?;
+
+        let level_str = level.to_string();
+
+        let cmd = HeosCommand::new()
+            .group("group")
+            .cmd("set_volume")
+            .attr("level", &level_str);
+
+        match self.send_command(&cmd).await? {
+            HeosReply::Volume(true, _) => {
+                self.volume = level.into();
+
+                Ok(())
+            },
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn volume_up(&mut self, step: u8) -> Result<()> {
+        check_range("step", step, 1..=10)?;
+
+        let step_str = step.to_string();
+
+        let cmd = HeosCommand::new()
+            .group("group")
+            .cmd("volume_up")
+            .attr("step", &step_str);
+
+        match self.send_command(&cmd).await? {
+            /* Device reports the new level with a volume changed event */
+            HeosReply::Volume(true, _) => Ok(()),
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn volume_down(&mut self, step: u8) -> Result<()> {
+        check_range("step", step, 1..=10)?;
+
+        let step_str = step.to_string();
+
+        let cmd = HeosCommand::new()
+            .group("group")
+            .cmd("volume_down")
+            .attr("step", &step_str);
+
+        match self.send_command(&cmd).await? {
+            /* Device reports the new level with a volume changed event */
+            HeosReply::Volume(true, _) => Ok(()),
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn get_mute(&mut self) -> Result<bool> {
+        let cmd = HeosCommand::new()
+            .group("group")
+            .cmd("get_mute");
+
+        match self.send_command(&cmd).await? {
+            HeosReply::Mute(true, attrs) => {
+                self.muted = parse_on_off(HeosReply::get_attr(&attrs, "state")?)?;
+
+                Ok(self.muted)
+            },
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn set_mute(&mut self, mute: bool) -> Result<()> {
+        let cmd = HeosCommand::new()
+            .group("group")
+            .cmd("set_mute")
+            .attr("state", format_on_off(mute));
+
+        match self.send_command(&cmd).await? {
+            HeosReply::Mute(true, _) => {
+                self.muted = mute;
+
+                Ok(())
+            },
+            reply => Err(reply.into_error()),
+        }
+    }
+
+    pub async fn toggle_mute(&mut self) -> Result<()> {
+        let cmd = HeosCommand::new()
+            .group("group")
+            .cmd("toggle_mute");
+
+        match self.send_command(&cmd).await? {
+            HeosReply::Mute(true, _) => {
+                self.muted = !self.muted;
+
+                Ok(())
+            },
+            reply => Err(reply.into_error()),
+        }
+    }
+}
+
+impl HeosCommandHandler for HeosGroup {
+    async fn send_command<'a>(&mut self, cmd: &HeosCommand<'a>) -> Result<HeosReply> {
+
+        /* Append group id */
+        let group_cmd = cmd.clone().attr("gid", self.group_id.as_str());
+
+        match self.leader {
+            Some(ref mut leader) => leader.send_command(&group_cmd).await,
+            None => Err(HeosError::NoLeader),
         }
     }
 }
@@ -78,6 +282,7 @@ impl Clone for HeosGroup {
             leader: self.leader.clone(),
             players: None,
             volume: self.volume,
+            muted: self.muted,
         }
     }
 }
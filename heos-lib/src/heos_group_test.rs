@@ -13,13 +13,33 @@
 mod heos_group_test {
     use pretty_assertions::assert_eq;
     use rstest::{fixture, rstest};
+    use tokio::sync::mpsc::UnboundedReceiver;
+    use crate::heos_error::HeosError;
     use crate::heos_group::HeosGroup;
+    use crate::heos_mock::{as_line, mock_connection};
+    use crate::{test_asset, HeosDevice};
 
     #[fixture]
     fn heos_group() -> HeosGroup {
         HeosGroup::new(env!("TEST_GROUP_NAME"), "")
     }
 
+    async fn mock_heos_group(response: &str) -> (HeosGroup, UnboundedReceiver<String>) {
+        let (connection, commands) = mock_connection(1, vec![as_line(response)]).await;
+
+        let mut leader = HeosDevice::new(env!("TEST_DEVICE_NAME"),
+                                         env!("TEST_DEVICE_IP"), env!("TEST_GROUP_LEADER"))
+            .expect("Failed to create device");
+
+        leader.connection = Some(connection);
+
+        let mut heos_group = HeosGroup::new(env!("TEST_GROUP_NAME"), env!("TEST_GROUP_LEADER"));
+
+        heos_group.leader = Some(leader);
+
+        (heos_group, commands)
+    }
+
     #[rstest]
     fn should_clone_and_compare_with_itself(heos_group: HeosGroup) {
         let cloned = heos_group.clone();
@@ -27,6 +47,169 @@ mod heos_group_test {
         assert_eq!(heos_group, cloned);
     }
 
+    #[tokio::test]
+    async fn should_set_members() {
+        let (mut heos_group, mut commands) = mock_heos_group(
+            test_asset!("set_group.json")).await;
+
+        heos_group.set_members(&["-1441236068"]).await
+            .expect("Failed to set members");
+
+        assert_eq!(heos_group.name, "1st Floor + Kitchen");
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   format!("heos://group/set_group?pid={},-1441236068", env!("TEST_GROUP_LEADER")));
+    }
+
+    #[tokio::test]
+    async fn should_add_members_to_existing_ones() {
+        let (mut heos_group, mut commands) = mock_heos_group(
+            test_asset!("set_group.json")).await;
+
+        let leader = heos_group.leader.clone()
+            .expect("Failed to get leader");
+        let member = HeosDevice::new("Kitchen", "", "-1441236068")
+            .expect("Failed to create device");
+
+        heos_group.players = Some(vec![leader, member]);
+
+        heos_group.add_members(&["-1441236068", "1735248321"]).await
+            .expect("Failed to add members");
+
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   format!("heos://group/set_group?pid={},-1441236068,1735248321", env!("TEST_GROUP_LEADER")));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn should_reject_adding_no_members(mut heos_group: HeosGroup) {
+        assert!(matches!(heos_group.add_members(&[]).await,
+            Err(HeosError::InvalidArgument(_))));
+    }
+
+    #[tokio::test]
+    async fn should_ungroup() {
+        let (mut heos_group, mut commands) = mock_heos_group(
+            test_asset!("set_group_ungroup.json")).await;
+
+        heos_group.ungroup().await
+            .expect("Failed to ungroup");
+
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   format!("heos://group/set_group?pid={}", env!("TEST_GROUP_LEADER")));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn should_reject_group_without_members(mut heos_group: HeosGroup) {
+        assert!(matches!(heos_group.set_members(&[]).await,
+            Err(HeosError::InvalidArgument(_))));
+    }
+
+    #[tokio::test]
+    async fn should_play_preset_on_leader() {
+        let (mut heos_group, mut commands) = mock_heos_group(
+            test_asset!("play_preset.json")).await;
+
+        heos_group.play_preset(3).await
+            .expect("Failed to play preset");
+
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   format!("heos://browse/play_preset?pid={}&preset=3", env!("TEST_GROUP_LEADER")));
+    }
+
+    #[tokio::test]
+    async fn should_update_info() {
+        let (mut heos_group, mut commands) = mock_heos_group(
+            test_asset!("group_get_group_info.json")).await;
+
+        heos_group.update_info().await
+            .expect("Failed to update info");
+
+        assert_eq!(heos_group.name, "1st Floor + Kitchen");
+        assert_eq!(heos_group.players.as_ref().map(|players| players.len()), Some(2));
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   format!("heos://group/get_group_info?gid={}", env!("TEST_GROUP_LEADER")));
+    }
+
+    #[tokio::test]
+    async fn should_keep_connection_for_new_leader_without_address() {
+        let (mut heos_group, _) = mock_heos_group(
+            test_asset!("group_get_group_info_new_leader.json")).await;
+
+        heos_group.update_info().await
+            .expect("Failed to update info");
+
+        let leader = heos_group.leader.as_ref()
+            .expect("Failed to get leader");
+
+        assert_eq!(leader.player_id, "-1441236068");
+        assert_eq!(leader.name, "Kitchen");
+        assert!(leader.connection.is_some());
+    }
+
+    #[tokio::test]
+    async fn should_rebuild_new_leader_with_address() {
+        let (mut heos_group, _) = mock_heos_group(
+            test_asset!("group_get_group_info_new_leader_ip.json")).await;
+
+        heos_group.update_info().await
+            .expect("Failed to update info");
+
+        let leader = heos_group.leader.as_ref()
+            .expect("Failed to get leader");
+
+        assert_eq!(leader.player_id, "-1441236068");
+        assert_eq!(leader.base_url, "10.0.8.37");
+        assert!(leader.connection.is_none());
+    }
+
+    #[tokio::test]
+    async fn should_update_mute() {
+        let (mut heos_group, _) = mock_heos_group(
+            test_asset!("group_get_mute.json")).await;
+
+        heos_group.update_mute().await
+            .expect("Failed to update mute");
+
+        assert!(heos_group.muted);
+    }
+
+    #[tokio::test]
+    async fn should_get_volume() {
+        let (mut heos_group, mut commands) = mock_heos_group(
+            test_asset!("group_get_volume.json")).await;
+
+        let level = heos_group.get_volume().await
+            .expect("Failed to get volume");
+
+        assert_eq!(level, 30);
+        assert_eq!(heos_group.volume, 30);
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   format!("heos://group/get_volume?gid={}", env!("TEST_GROUP_LEADER")));
+    }
+
+    #[tokio::test]
+    async fn should_step_volume_down() {
+        let (mut heos_group, mut commands) = mock_heos_group(
+            test_asset!("group_volume_down.json")).await;
+
+        heos_group.volume = 3;
+
+        heos_group.volume_down(5).await
+            .expect("Failed to lower volume");
+
+        /* Level is only known once the device reports it */
+        assert_eq!(heos_group.volume, 3);
+        assert_eq!(commands.recv().await.expect("Failed to receive command"),
+                   format!("heos://group/volume_down?step=5&gid={}", env!("TEST_GROUP_LEADER")));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn should_fail_without_leader(mut heos_group: HeosGroup) {
+        assert!(matches!(heos_group.toggle_mute().await, Err(HeosError::NoLeader)));
+    }
+
     #[ignore]
     #[rstest]
     #[tokio::test]
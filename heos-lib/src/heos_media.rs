@@ -9,18 +9,212 @@
 /// See the file LICENSE for details.
 ///
 
-#[derive(Default, Clone, PartialEq, Debug)]
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, Instant};
+use crate::heos_browse::ServiceOption;
+use crate::heos_state::PlayState;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum HeosMediaSourceType {
+    Pandora,
+    Rhapsody,
+    TuneIn,
+    Spotify,
+    Deezer,
+    Napster,
+    IHeartRadio,
+    SiriusXm,
+    Soundcloud,
+    Tidal,
+    Rdio,
+    AmazonMusic,
+    Moodmix,
+    Juke,
+    QqMusic,
+    LocalMedia,
+    Playlists,
+    History,
+    AuxInput,
+    Favorites,
+    Unknown(u32),
+}
+
+impl Default for HeosMediaSourceType {
+    fn default() -> Self {
+        HeosMediaSourceType::Unknown(0)
+    }
+}
+
+impl HeosMediaSourceType {
+    pub fn from_sid(sid: u32) -> Self {
+        match sid {
+            1 => HeosMediaSourceType::Pandora,
+            2 => HeosMediaSourceType::Rhapsody,
+            3 => HeosMediaSourceType::TuneIn,
+            4 => HeosMediaSourceType::Spotify,
+            5 => HeosMediaSourceType::Deezer,
+            6 => HeosMediaSourceType::Napster,
+            7 => HeosMediaSourceType::IHeartRadio,
+            8 => HeosMediaSourceType::SiriusXm,
+            9 => HeosMediaSourceType::Soundcloud,
+            10 => HeosMediaSourceType::Tidal,
+            12 => HeosMediaSourceType::Rdio,
+            13 => HeosMediaSourceType::AmazonMusic,
+            15 => HeosMediaSourceType::Moodmix,
+            16 => HeosMediaSourceType::Juke,
+            18 => HeosMediaSourceType::QqMusic,
+            1024 => HeosMediaSourceType::LocalMedia,
+            1025 => HeosMediaSourceType::Playlists,
+            1026 => HeosMediaSourceType::History,
+            1027 => HeosMediaSourceType::AuxInput,
+            1028 => HeosMediaSourceType::Favorites,
+            sid => HeosMediaSourceType::Unknown(sid),
+        }
+    }
+
+    pub fn sid(&self) -> u32 {
+        match self {
+            HeosMediaSourceType::Pandora => 1,
+            HeosMediaSourceType::Rhapsody => 2,
+            HeosMediaSourceType::TuneIn => 3,
+            HeosMediaSourceType::Spotify => 4,
+            HeosMediaSourceType::Deezer => 5,
+            HeosMediaSourceType::Napster => 6,
+            HeosMediaSourceType::IHeartRadio => 7,
+            HeosMediaSourceType::SiriusXm => 8,
+            HeosMediaSourceType::Soundcloud => 9,
+            HeosMediaSourceType::Tidal => 10,
+            HeosMediaSourceType::Rdio => 12,
+            HeosMediaSourceType::AmazonMusic => 13,
+            HeosMediaSourceType::Moodmix => 15,
+            HeosMediaSourceType::Juke => 16,
+            HeosMediaSourceType::QqMusic => 18,
+            HeosMediaSourceType::LocalMedia => 1024,
+            HeosMediaSourceType::Playlists => 1025,
+            HeosMediaSourceType::History => 1026,
+            HeosMediaSourceType::AuxInput => 1027,
+            HeosMediaSourceType::Favorites => 1028,
+            HeosMediaSourceType::Unknown(sid) => *sid,
+        }
+    }
+}
+
+impl Display for HeosMediaSourceType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            HeosMediaSourceType::Pandora => "Pandora",
+            HeosMediaSourceType::Rhapsody => "Rhapsody",
+            HeosMediaSourceType::TuneIn => "TuneIn",
+            HeosMediaSourceType::Spotify => "Spotify",
+            HeosMediaSourceType::Deezer => "Deezer",
+            HeosMediaSourceType::Napster => "Napster",
+            HeosMediaSourceType::IHeartRadio => "iHeartRadio",
+            HeosMediaSourceType::SiriusXm => "SiriusXM",
+            HeosMediaSourceType::Soundcloud => "SoundCloud",
+            HeosMediaSourceType::Tidal => "Tidal",
+            HeosMediaSourceType::Rdio => "Rdio",
+            HeosMediaSourceType::AmazonMusic => "Amazon Music",
+            HeosMediaSourceType::Moodmix => "Moodmix",
+            HeosMediaSourceType::Juke => "Juke",
+            HeosMediaSourceType::QqMusic => "QQMusic",
+            HeosMediaSourceType::LocalMedia => "Local Media",
+            HeosMediaSourceType::Playlists => "HEOS Playlists",
+            HeosMediaSourceType::History => "HEOS History",
+            HeosMediaSourceType::AuxInput => "HEOS AUX Inputs",
+            HeosMediaSourceType::Favorites => "HEOS Favorites",
+            HeosMediaSourceType::Unknown(_) => "Unknown source",
+        })
+    }
+}
+
+#[derive(Default, Clone, PartialEq, Debug)]
+pub enum HeosMediaType {
     #[default]
-    Player,
-    Station,
+    Song,
+    Station {
+        station_name: String,
+    },
 }
 
 #[derive(Default, Clone, PartialEq, Debug)]
 pub struct HeosMedia {
+    pub media_type: HeosMediaType,
     pub source_type: HeosMediaSourceType,
     pub artist_title: String,
     pub song_title: String,
     pub album_title: String,
     pub image_url: String,
-}
\ No newline at end of file
+    pub media_id: String,
+    pub album_id: String,
+    pub queue_id: Option<u32>,
+    pub duration: Option<Duration>,
+    pub options: Vec<ServiceOption>,
+}
+
+impl Display for HeosMedia {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.media_type {
+            HeosMediaType::Song => write!(f, "{} - {} ({})",
+                                          self.artist_title, self.song_title, self.album_title),
+            HeosMediaType::Station { ref station_name } => write!(f, "{}: {} - {}",
+                                                                  station_name, self.artist_title, self.song_title),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct HeosProgress {
+    pub cur_pos: Duration,
+    pub duration: Duration,
+    pub state: PlayState,
+    pub updated_at: Instant,
+}
+
+impl HeosProgress {
+    pub fn new(cur_pos_ms: u64, duration_ms: u64) -> Self {
+        Self {
+            cur_pos: Duration::from_millis(cur_pos_ms),
+            duration: Duration::from_millis(duration_ms),
+            state: PlayState::Play,
+            updated_at: Instant::now(),
+        }
+    }
+
+    pub fn position(&self) -> Duration {
+        self.position_at(Instant::now())
+    }
+
+    pub fn position_at(&self, now: Instant) -> Duration {
+        /* Devices only send progress every few seconds, so interpolate locally */
+        let pos = match self.state {
+            PlayState::Play => self.cur_pos + now.saturating_duration_since(self.updated_at),
+            _ => self.cur_pos,
+        };
+
+        /* Streams have no duration */
+        match self.duration.is_zero() {
+            true => pos,
+            false => pos.min(self.duration),
+        }
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.duration.saturating_sub(self.position())
+    }
+
+    pub fn ratio(&self) -> f64 {
+        match self.duration.is_zero() {
+            true => 0.0,
+            false => self.position().as_secs_f64() / self.duration.as_secs_f64(),
+        }
+    }
+
+    pub fn set_state(&mut self, state: PlayState) {
+        /* Freeze the interpolated position when leaving play and resume from there */
+        let now = Instant::now();
+
+        self.cur_pos = self.position_at(now);
+        self.updated_at = now;
+        self.state = state;
+    }
+}
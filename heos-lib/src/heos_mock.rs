@@ -0,0 +1,67 @@
+///
+/// @package heos-dial
+///
+/// @file HEOS mock device for tests
+/// @copyright (c) 2024-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv3.
+/// See the file LICENSE for details.
+///
+
+use crate::heos_connection::HeosConnection;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+
+pub(crate) fn as_line(asset: &str) -> String {
+    format!("{}\r\n", gjson::parse(asset).json().replace(['\r', '\n'], ""))
+}
+
+pub(crate) async fn mock_device(expected: usize, responses: Vec<String>)
+    -> (String, mpsc::UnboundedReceiver<String>)
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await
+        .expect("Failed to bind mock device");
+    let addr = listener.local_addr()
+        .expect("Failed to get mock address").to_string();
+
+    let (sender, receiver) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        let (stream, _) = listener.accept().await
+            .expect("Failed to accept connection");
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+
+        /* Wait for all commands before answering */
+        for _ in 0..expected {
+            let line = lines.next_line().await
+                .expect("Failed to read command")
+                .expect("Connection closed");
+
+            let _ = sender.send(line);
+        }
+
+        for response in responses {
+            writer.write_all(response.as_bytes()).await
+                .expect("Failed to write response");
+        }
+
+        /* Keep connection open until client is done */
+        let _ = lines.next_line().await;
+    });
+
+    (addr, receiver)
+}
+
+pub(crate) async fn mock_connection(expected: usize, responses: Vec<String>)
+    -> (HeosConnection, mpsc::UnboundedReceiver<String>)
+{
+    let (addr, receiver) = mock_device(expected, responses).await;
+
+    let connection = HeosConnection::connect(&addr).await
+        .expect("Failed to connect to mock device");
+
+    (connection, receiver)
+}
\ No newline at end of file
@@ -0,0 +1,29 @@
+///
+/// @package heos-dial
+///
+/// @file HEOS lib
+/// @copyright (c) 2024-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv3.
+/// See the file LICENSE for details.
+///
+
+use std::fmt::{Display, Formatter};
+
+#[derive(Default, Clone, PartialEq, Debug)]
+pub struct HeosQueueItem {
+    pub queue_id: u32,
+    pub song_title: String,
+    pub album_title: String,
+    pub artist_title: String,
+    pub image_url: String,
+    pub media_id: String,
+    pub album_id: String,
+}
+
+impl Display for HeosQueueItem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}. {} - {}", self.queue_id, self.artist_title, self.song_title)
+    }
+}
@@ -9,10 +9,17 @@
 /// See the file LICENSE for details.
 ///
 
-use anyhow::{anyhow, Result};
 use gjson::Value;
 use std::collections::HashMap;
+use std::time::Duration;
+use crate::constants::MSG_UNDER_PROCESS;
+use crate::heos_command::decode_value;
+use crate::heos_error::{HeosError, Result};
+use crate::heos_browse::{HeosBrowseItem, HeosContainer, HeosMediaItem, HeosSearchCriteria, HeosSource, ServiceOption};
 use crate::heos_group::HeosGroup;
+use crate::heos_state::AccountState;
+use crate::heos_media::{HeosMedia, HeosMediaSourceType, HeosMediaType};
+use crate::heos_queue::HeosQueueItem;
 use crate::HeosDevice;
 
 #[derive(Clone, PartialEq, Debug)]
@@ -23,9 +30,23 @@ pub enum HeosReply {
     GroupInfo(bool, HeosGroup),
     PlayState(bool, HashMap<String, String>),
     PlayAction(bool, HashMap<String, String>),
-    PlayingMedia(bool, HashMap<String, String>),
+    PlayMode(bool, HashMap<String, String>),
+    PlayingMedia(bool, Option<HeosMedia>),
+    Queue(bool, HashMap<String, String>, Vec<HeosQueueItem>),
+    QueueAction(bool, HashMap<String, String>),
     Volume(bool, HashMap<String, String>),
     Mute(bool, HashMap<String, String>),
+    ChangeEvents(bool, HashMap<String, String>),
+    GroupSet(bool, HashMap<String, String>),
+    MusicSources(bool, Vec<HeosSource>),
+    SourceInfo(bool, HeosSource),
+    Browse(bool, HashMap<String, String>, Vec<HeosBrowseItem>),
+    SearchCriteria(bool, Vec<HeosSearchCriteria>),
+    Search(bool, HashMap<String, String>, Vec<HeosBrowseItem>),
+    ServiceOptions(bool, Vec<ServiceOption>),
+    Account(bool, AccountState),
+    ServiceOptionSet(bool, HashMap<String, String>),
+    UnderProcess(String),
     Error(bool, String, HashMap<String, String>),
 }
 
@@ -39,13 +60,18 @@ impl HeosReply {
                 Self::parse_message(&json, "heos.message")))
         }
 
+        /* Check for interim reply of long-running commands */
+        if Self::is_under_process(response_str) {
+            return Ok(HeosReply::UnderProcess(json.get("heos.command").to_string()))
+        }
+
         match json.get("heos.command").str() {
             "player/get_players" => Ok(HeosReply::Players(
                 "success" == json.get("heos.result").str(),
                 Self::parse_players_payload(&json, "payload", "")
             )),
 
-            "player/get_groups" => Ok(HeosReply::Groups(
+            "player/get_groups" | "group/get_groups" => Ok(HeosReply::Groups(
                 "success" == json.get("heos.result").str(),
                 Self::parse_groups_payload(&json, "payload")
             )),
@@ -55,7 +81,8 @@ impl HeosReply {
                 Self::parse_player(&json.get("payload"))
             )),
 
-            "player/get_group_info" => Ok(HeosReply::GroupInfo(
+            /* Older firmware lists group commands in the player group */
+            "player/get_group_info" | "group/get_group_info" => Ok(HeosReply::GroupInfo(
                 "success" == json.get("heos.result").str(),
                 Self::parse_group(&json.get("payload"))
             )),
@@ -65,18 +92,40 @@ impl HeosReply {
                 Self::parse_message(&json, "heos.message")
             )),
 
-            "player/play_next" | "player/play_previous" => Ok(HeosReply::PlayAction(
+            "player/play_next" | "player/play_previous"
+            | "browse/play_preset" | "browse/play_stream"
+            | "browse/play_input" => Ok(HeosReply::PlayAction(
+                "success" == json.get("heos.result").str(),
+                Self::parse_message(&json, "heos.message")
+            )),
+
+            "player/get_play_mode" | "player/set_play_mode" => Ok(HeosReply::PlayMode(
                 "success" == json.get("heos.result").str(),
                 Self::parse_message(&json, "heos.message")
             )),
 
             "player/get_now_playing_media" => Ok(HeosReply::PlayingMedia(
                 "success" == json.get("heos.result").str(),
-                Self::parse_generic_payload(&json, "payload")
+                Self::parse_now_playing(&json)
+            )),
+
+            "player/get_queue" => Ok(HeosReply::Queue(
+                "success" == json.get("heos.result").str(),
+                Self::parse_message(&json, "heos.message"),
+                Self::parse_queue_payload(&json, "payload")
+            )),
+
+            "player/play_queue" | "player/remove_from_queue" | "player/save_queue"
+            | "player/clear_queue" | "player/move_queue_item"
+            | "browse/add_to_queue" => Ok(HeosReply::QueueAction(
+                "success" == json.get("heos.result").str(),
+                Self::parse_message(&json, "heos.message")
             )),
 
             "player/set_volume" | "player/get_volume"
-            | "group/set_volume" | "group/get_volume" => Ok(HeosReply::Volume(
+            | "player/volume_up" | "player/volume_down"
+            | "group/set_volume" | "group/get_volume"
+            | "group/volume_up" | "group/volume_down" => Ok(HeosReply::Volume(
                 "success" == json.get("heos.result").str(),
                 Self::parse_message(&json, "heos.message")
             )),
@@ -87,16 +136,87 @@ impl HeosReply {
                 Self::parse_message(&json, "heos.message")
             )),
 
-            cmd => Err(anyhow!("Command type `{:?}` unknown", cmd)),
+            "group/set_group" | "player/set_group" => Ok(HeosReply::GroupSet(
+                "success" == json.get("heos.result").str(),
+                Self::parse_message(&json, "heos.message")
+            )),
+
+            "system/register_for_change_events" => Ok(HeosReply::ChangeEvents(
+                "success" == json.get("heos.result").str(),
+                Self::parse_message(&json, "heos.message")
+            )),
+
+            "browse/get_music_sources" => Ok(HeosReply::MusicSources(
+                "success" == json.get("heos.result").str(),
+                Self::parse_sources_payload(&json, "payload")
+            )),
+
+            "browse/get_source_info" => Ok(HeosReply::SourceInfo(
+                "success" == json.get("heos.result").str(),
+                Self::parse_source(&json.get("payload"))
+            )),
+
+            "browse/browse" => Ok(HeosReply::Browse(
+                "success" == json.get("heos.result").str(),
+                Self::parse_message(&json, "heos.message"),
+                Self::parse_browse_payload(&json, "payload")
+            )),
+
+            "browse/get_search_criteria" => Ok(HeosReply::SearchCriteria(
+                "success" == json.get("heos.result").str(),
+                Self::parse_search_criteria_payload(&json, "payload")
+            )),
+
+            "browse/search" | "browse/multi_search" => Ok(HeosReply::Search(
+                "success" == json.get("heos.result").str(),
+                Self::parse_message(&json, "heos.message"),
+                Self::parse_browse_payload(&json, "payload")
+            )),
+
+            "system/check_account" | "system/sign_in" | "system/sign_out" => Ok(HeosReply::Account(
+                "success" == json.get("heos.result").str(),
+                AccountState::from_message(json.get("heos.message").str(),
+                                           Self::parse_message(&json, "heos.message").get("un"))
+            )),
+
+            "browse/get_service_options" => Ok(HeosReply::ServiceOptions(
+                "success" == json.get("heos.result").str(),
+                Self::parse_service_options(&json, "payload")
+            )),
+
+            "browse/set_service_option" => Ok(HeosReply::ServiceOptionSet(
+                "success" == json.get("heos.result").str(),
+                Self::parse_message(&json, "heos.message")
+            )),
+
+            cmd => Err(HeosError::UnknownCommand(cmd.into())),
         }
     }
 
+    pub fn is_under_process(response_str: &str) -> bool {
+        gjson::get(response_str, "heos.message").str().starts_with(MSG_UNDER_PROCESS)
+    }
+
+    pub fn into_error(self) -> HeosError {
+        match self {
+            HeosReply::Error(_, command, message) => HeosError::from_message(&command, &message),
+            reply => HeosError::UnexpectedReply(format!("{:?}", reply)),
+        }
+    }
+
+    pub(crate) fn get_attr<'a>(attrs: &'a HashMap<String, String>, key: &str) -> Result<&'a str> {
+        attrs.get(key)
+            .map(|value| value.as_str())
+            .ok_or_else(|| HeosError::Parse(format!("Attribute `{}` missing", key)))
+    }
+
     pub(crate) fn parse_message(json: &Value, path: &str) -> HashMap<String, String> {
+        /* Values are percent-encoded, the separators are not */
         json.get(path).str()
             .split("&")
             .filter_map(|s| {
                 s.split_once("=")
-                    .and_then(|t| Some((t.0.to_owned(), t.1.to_owned())))
+                    .map(|t| (t.0.to_owned(), decode_value(t.1)))
             })
             .collect()
     }
@@ -112,11 +232,108 @@ impl HeosReply {
     }
 
     pub fn parse_group(json: &Value) -> HeosGroup {
-        HeosGroup::new(json.get("name").str(),
-                       json.get("gid").str())
+        let mut group = HeosGroup::new(json.get("name").str(),
+                                       json.get("gid").str());
+
+        group.players = Some(Self::parse_players_payload(json, "players", &group.group_id));
+
+        group.leader = json.get("players").array().iter()
+            .find(|player| "leader" == player.get("role").str())
+            .map(Self::parse_player);
+
+        group
     }
 
 
+    pub fn parse_source(json: &Value) -> HeosSource {
+        HeosSource {
+            name: json.get("name").to_string(),
+            image_url: json.get("image_url").to_string(),
+            source_type: json.get("type").to_string(),
+            source_id: json.get("sid").to_string(),
+            available: "true" == json.get("available").str(),
+            service_username: match json.get("service_username").exists() {
+                true => Some(json.get("service_username").to_string()),
+                false => None,
+            },
+        }
+    }
+
+    pub fn parse_container(json: &Value) -> HeosContainer {
+        HeosContainer {
+            name: json.get("name").to_string(),
+            image_url: json.get("image_url").to_string(),
+            container_type: json.get("type").to_string(),
+            container_id: json.get("cid").to_string(),
+            media_id: match json.get("mid").exists() {
+                true => Some(json.get("mid").to_string()),
+                false => None,
+            },
+            playable: "yes" == json.get("playable").str(),
+        }
+    }
+
+    pub fn parse_media_item(json: &Value) -> HeosMediaItem {
+        HeosMediaItem {
+            name: json.get("name").to_string(),
+            image_url: json.get("image_url").to_string(),
+            media_type: json.get("type").to_string(),
+            media_id: json.get("mid").to_string(),
+            artist_title: json.get("artist").to_string(),
+            album_title: json.get("album").to_string(),
+            playable: "yes" == json.get("playable").str(),
+        }
+    }
+
+
+    pub fn parse_now_playing(json: &Value) -> Option<HeosMedia> {
+        let payload = Self::parse_generic_payload(json, "payload");
+
+        let get = |key: &str| payload.get(key).cloned().unwrap_or_default();
+
+        /* Nothing is playing */
+        let media_type = match payload.get("type")?.as_str() {
+            "station" => HeosMediaType::Station {
+                station_name: get("station"),
+            },
+            _ => HeosMediaType::Song,
+        };
+
+        Some(HeosMedia {
+            media_type,
+            source_type: HeosMediaSourceType::from_sid(get("sid").parse().unwrap_or_default()),
+            artist_title: get("artist"),
+            song_title: get("song"),
+            album_title: get("album"),
+            image_url: get("image_url"),
+            media_id: get("mid"),
+            album_id: get("album_id"),
+            queue_id: payload.get("qid").and_then(|qid| qid.parse().ok()),
+            duration: payload.get("duration")
+                .and_then(|duration| duration.parse().ok())
+                .map(Duration::from_millis),
+            options: Self::parse_service_options(json, "options"),
+        })
+    }
+
+    pub(crate) fn parse_service_options(json: &Value, path: &str) -> Vec<ServiceOption> {
+        /* Options are grouped by context like `play`, flatten them */
+        json.get(path).array().iter()
+            .flat_map(|group| {
+                let mut options = vec![];
+
+                group.each(|_, values| {
+                    options.extend(values.array().iter()
+                        .map(|option| ServiceOption::from_id(option.get("id").u32())));
+
+                    true
+                });
+
+                options
+            })
+            .collect()
+    }
+
     pub(crate) fn parse_generic_payload(json: &Value, path: &str) -> HashMap<String, String> {
         let mut payload: HashMap<String, String> = HashMap::new();
 
@@ -139,23 +356,56 @@ impl HeosReply {
             .collect()
     }
 
-    pub(crate) fn parse_groups_payload(json: &Value, path: &str) -> Vec<HeosGroup> {
+    pub(crate) fn parse_queue_payload(json: &Value, path: &str) -> Vec<HeosQueueItem> {
         json.get(path).array().iter()
-            .map(|v| {
-                let mut group = Self::parse_group(v);
+            .map(|v| HeosQueueItem {
+                queue_id: v.get("qid").u32(),
+                song_title: v.get("song").to_string(),
+                album_title: v.get("album").to_string(),
+                artist_title: v.get("artist").to_string(),
+                image_url: v.get("image_url").to_string(),
+                media_id: v.get("mid").to_string(),
+                album_id: v.get("album_id").to_string(),
+            })
+            .collect()
+    }
 
-                group.players = Some(Self::parse_players_payload(v, "players", &*group.group_id));
+    pub(crate) fn parse_sources_payload(json: &Value, path: &str) -> Vec<HeosSource> {
+        json.get(path).array().iter()
+            .map(Self::parse_source)
+            .collect()
+    }
 
-                v.get("players").array().iter().for_each(|player| {
-                    if "leader".eq(player.get("role").str()) {
-                        group.leader = Some(Self::parse_player(player));
+    pub(crate) fn parse_search_criteria_payload(json: &Value, path: &str) -> Vec<HeosSearchCriteria> {
+        json.get(path).array().iter()
+            .map(|v| HeosSearchCriteria {
+                name: v.get("name").to_string(),
+                criteria_id: v.get("scid").to_string(),
+                wildcard: "yes" == v.get("wildcard").str(),
+                playable: "yes" == v.get("playable").str(),
+                container_id: match v.get("cid").exists() {
+                    true => Some(v.get("cid").to_string()),
+                    false => None,
+                },
+            })
+            .collect()
+    }
 
-                        return;
-                    }
-                });
+    pub(crate) fn parse_browse_payload(json: &Value, path: &str) -> Vec<HeosBrowseItem> {
+        json.get(path).array().iter()
+            .map(|v| match v.get("container").str() {
+                "yes" => HeosBrowseItem::Container(Self::parse_container(v)),
+                "no" => HeosBrowseItem::Media(Self::parse_media_item(v)),
 
-                group
+                /* Aggregated sources like local media list further sources */
+                _ => HeosBrowseItem::Source(Self::parse_source(v)),
             })
             .collect()
     }
+
+    pub(crate) fn parse_groups_payload(json: &Value, path: &str) -> Vec<HeosGroup> {
+        json.get(path).array().iter()
+            .map(Self::parse_group)
+            .collect()
+    }
 }
@@ -12,7 +12,13 @@
 #[cfg(test)]
 mod heos_reply_test {
     use std::collections::HashMap;
+    use std::time::Duration;
+    use crate::heos_browse::{HeosBrowseItem, HeosSource, ServiceOption};
+    use crate::heos_media::{HeosMediaSourceType, HeosMediaType};
+    use crate::heos_queue::HeosQueueItem;
+    use crate::heos_command::HeosCommand;
     use crate::heos_reply::HeosReply;
+    use crate::heos_state::AccountState;
     use pretty_assertions::assert_eq;
     use crate::test_asset;
 
@@ -68,6 +74,7 @@ mod heos_reply_test {
         if let HeosReply::GroupInfo(success, group) = reply {
             assert!(success);
             assert_eq!(group.name, env!("TEST_GROUP_NAME"));
+            assert_eq!(group.leader.map(|leader| leader.player_id), Some("844263156".into()));
         } else {
             panic!("Wrong reply type");
         }
@@ -105,6 +112,53 @@ mod heos_reply_test {
         assert!(matches!(reply, HeosReply::PlayAction { .. }));
     }
 
+    #[test]
+    fn should_parse_set_group_reply() {
+        let reply = HeosReply::parse(test_asset!("set_group.json"))
+            .expect("Failed to parse set_group.json");
+
+        if let HeosReply::GroupSet(success, message) = reply {
+            assert!(success);
+            assert_eq!(message.get("gid").expect("Parsing gid failed"), "-1859434560");
+        } else {
+            panic!("Expected set group reply");
+        }
+    }
+
+    #[test]
+    fn should_parse_group_command_aliases() {
+        let groups_json = test_asset!("get_groups.json")
+            .replace("player/get_groups", "group/get_groups");
+        let set_group_json = test_asset!("set_group.json")
+            .replace("group/set_group", "player/set_group");
+
+        assert!(matches!(HeosReply::parse(&groups_json)
+            .expect("Failed to parse group/get_groups"), HeosReply::Groups(true, _)));
+        assert!(matches!(HeosReply::parse(&set_group_json)
+            .expect("Failed to parse player/set_group"), HeosReply::GroupSet(true, _)));
+    }
+
+    #[test]
+    fn should_parse_play_preset_reply() {
+        let reply = HeosReply::parse(test_asset!("play_preset.json"))
+            .expect("Failed to parse play_preset.json");
+
+        assert!(matches!(reply, HeosReply::PlayAction { .. }));
+    }
+
+    #[test]
+    fn should_parse_play_mode_reply() {
+        let mut reply = HeosReply::parse(test_asset!("get_play_mode.json"))
+            .expect("Failed to parse get_play_mode.json");
+
+        assert!(matches!(reply, HeosReply::PlayMode { .. }));
+
+        reply = HeosReply::parse(test_asset!("set_play_mode.json"))
+            .expect("Failed to parse set_play_mode.json");
+
+        assert!(matches!(reply, HeosReply::PlayMode { .. }));
+    }
+
     #[test]
     fn should_parse_get_now_playing_media_reply() {
         let reply = HeosReply::parse(test_asset!("get_now_playing_media.json"))
@@ -113,6 +167,182 @@ mod heos_reply_test {
         assert!(matches!(reply, HeosReply::PlayingMedia { .. }));
     }
 
+    #[test]
+    fn should_parse_now_playing_station() {
+        let json = gjson::parse(test_asset!("get_now_playing_station.json"));
+        let media = HeosReply::parse_now_playing(&json)
+            .expect("Failed to parse station");
+
+        assert_eq!(media.media_type, HeosMediaType::Station {
+            station_name: "Rock Antenne".into(),
+        });
+        assert_eq!(media.source_type, HeosMediaSourceType::TuneIn);
+        assert_eq!(media.artist_title, "Motörhead");
+        assert_eq!(media.queue_id, None);
+        assert_eq!(media.duration, None);
+        assert_eq!(media.options.len(), 3);
+        assert_eq!(media.options, vec![ServiceOption::ThumbsUp, ServiceOption::ThumbsDown,
+                                       ServiceOption::AddToFavorites]);
+    }
+
+    #[test]
+    fn should_parse_now_playing_duration() {
+        let asset = test_asset!("get_now_playing_song.json")
+            .replace("\"album_id\"", "\"duration\": 354000, \"album_id\"");
+        let media = HeosReply::parse_now_playing(&gjson::parse(&asset))
+            .expect("Failed to parse song");
+
+        assert_eq!(media.duration, Some(Duration::from_secs(354)));
+    }
+
+    #[test]
+    fn should_parse_get_service_options_reply() {
+        let reply = HeosReply::parse(test_asset!("get_service_options.json"))
+            .expect("Failed to parse get_service_options.json");
+
+        if let HeosReply::ServiceOptions(success, options) = reply {
+            assert!(success);
+            assert_eq!(options, vec![ServiceOption::ThumbsUp, ServiceOption::ThumbsDown,
+                                     ServiceOption::CreateNewStation]);
+        } else {
+            panic!("Expected service options reply");
+        }
+    }
+
+    #[test]
+    fn should_map_service_option_ids() {
+        for option_id in [1, 2, 3, 4, 5, 6, 7, 8, 11, 12, 13, 19, 20] {
+            let option = ServiceOption::from_id(option_id);
+
+            assert!(!matches!(option, ServiceOption::Unknown(_)));
+            assert_eq!(option.id(), option_id);
+        }
+
+        assert_eq!(ServiceOption::from_id(9), ServiceOption::Unknown(9));
+    }
+
+    #[test]
+    fn should_parse_check_account_signed_out_reply() {
+        let reply = HeosReply::parse(test_asset!("check_account_signed_out.json"))
+            .expect("Failed to parse check_account_signed_out.json");
+
+        assert!(matches!(reply, HeosReply::Account(true, AccountState::SignedOut)));
+    }
+
+    #[test]
+    fn should_map_source_ids() {
+        for sid in [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 12, 13, 15, 16, 18, 1024, 1025, 1026, 1027, 1028] {
+            let source_type = HeosMediaSourceType::from_sid(sid);
+
+            assert!(!matches!(source_type, HeosMediaSourceType::Unknown(_)));
+            assert_eq!(source_type.sid(), sid);
+        }
+
+        assert_eq!(HeosMediaSourceType::from_sid(11), HeosMediaSourceType::Unknown(11));
+        assert_eq!(HeosMediaSourceType::default(), HeosMediaSourceType::Unknown(0));
+    }
+
+    #[test]
+    fn should_parse_get_queue_reply() {
+        let reply = HeosReply::parse(test_asset!("get_queue.json"))
+            .expect("Failed to parse get_queue.json");
+
+        if let HeosReply::Queue(success, message, items) = reply {
+            assert!(success);
+            assert_eq!(message.get("count").expect("Parsing count failed"), "12");
+            assert_eq!(items.len(), 3);
+        } else {
+            panic!("Expected queue reply");
+        }
+    }
+
+    #[test]
+    fn should_parse_queue_action_replies() {
+        for asset in [test_asset!("play_queue.json"), test_asset!("remove_from_queue.json"),
+            test_asset!("save_queue.json"), test_asset!("clear_queue.json"),
+            test_asset!("move_queue_item.json"), test_asset!("add_to_queue.json")]
+        {
+            let reply = HeosReply::parse(asset)
+                .expect("Failed to parse queue action");
+
+            assert!(matches!(reply, HeosReply::QueueAction(true, _)));
+        }
+    }
+
+    #[test]
+    fn should_parse_get_music_sources_reply() {
+        let reply = HeosReply::parse(test_asset!("get_music_sources.json"))
+            .expect("Failed to parse get_music_sources.json");
+
+        if let HeosReply::MusicSources(success, sources) = reply {
+            assert!(success);
+            assert_eq!(sources.len(), 4);
+            assert_eq!(sources[0].service_username.as_deref(), Some("unexist"));
+            assert_eq!(sources[1].source_id, HeosSource::LOCAL_MEDIA);
+            assert_eq!(sources[1].service_username, None);
+            assert!(!sources[3].available);
+        } else {
+            panic!("Expected music sources reply");
+        }
+    }
+
+    #[test]
+    fn should_parse_get_source_info_reply() {
+        let reply = HeosReply::parse(test_asset!("get_source_info.json"))
+            .expect("Failed to parse get_source_info.json");
+
+        if let HeosReply::SourceInfo(success, source) = reply {
+            assert!(success);
+            assert_eq!(source.name, "Local Music");
+            assert_eq!(source.source_type, "heos_server");
+        } else {
+            panic!("Expected source info reply");
+        }
+    }
+
+    #[test]
+    fn should_parse_browse_reply() {
+        let reply = HeosReply::parse(test_asset!("browse_source.json"))
+            .expect("Failed to parse browse_source.json");
+
+        if let HeosReply::Browse(success, message, items) = reply {
+            assert!(success);
+            assert_eq!(message.get("count").expect("Parsing count failed"), "2");
+            assert!(matches!(&items[0], HeosBrowseItem::Source(source)
+                if "1513473564" == source.source_id && "dlna_server" == source.source_type));
+        } else {
+            panic!("Expected browse reply");
+        }
+    }
+
+    #[test]
+    fn should_parse_get_search_criteria_reply() {
+        let reply = HeosReply::parse(test_asset!("get_search_criteria.json"))
+            .expect("Failed to parse get_search_criteria.json");
+
+        if let HeosReply::SearchCriteria(success, criteria) = reply {
+            assert!(success);
+            assert_eq!(criteria.len(), 3);
+            assert_eq!(criteria[0].container_id, None);
+            assert_eq!(criteria[2].criteria_id, "3");
+            assert!(criteria[2].wildcard && criteria[2].playable);
+            assert_eq!(criteria[2].result_container_id("Heroes").as_deref(),
+                       Some("SEARCHED_TRACKS-Heroes"));
+        } else {
+            panic!("Expected search criteria reply");
+        }
+    }
+
+    #[test]
+    fn should_parse_search_replies() {
+        for asset in [test_asset!("search.json"), test_asset!("multi_search.json")] {
+            let reply = HeosReply::parse(asset)
+                .expect("Failed to parse search reply");
+
+            assert!(matches!(reply, HeosReply::Search(true, _, items) if !items.is_empty()));
+        }
+    }
+
     #[test]
     fn should_parse_set_volume_reply() {
         let mut reply = HeosReply::parse(test_asset!("set_volume.json"))
@@ -139,6 +369,23 @@ mod heos_reply_test {
         assert!(matches!(reply, HeosReply::Volume { .. }));
     }
 
+    #[test]
+    fn should_parse_volume_step_reply() {
+        for asset in [test_asset!("volume_up.json"), test_asset!("volume_down.json"),
+                      test_asset!("group_volume_up.json"), test_asset!("group_volume_down.json")]
+        {
+            let reply = HeosReply::parse(asset)
+                .expect("Failed to parse volume step reply");
+
+            if let HeosReply::Volume(success, attrs) = reply {
+                assert!(success);
+                assert_eq!(attrs.get("step").expect("Parsing step failed"), "5");
+            } else {
+                panic!("Wrong reply type");
+            }
+        }
+    }
+
     #[test]
     fn should_parse_set_group_mute_reply() {
         let mut reply = HeosReply::parse(test_asset!("set_mute.json"))
@@ -178,6 +425,30 @@ mod heos_reply_test {
         assert!(matches!(reply, HeosReply::Mute { .. }));
     }
 
+    #[test]
+    fn should_parse_register_for_change_events_reply() {
+        let reply = HeosReply::parse(test_asset!("register_for_change_events.json"))
+            .expect("Failed to parse register_for_change_events.json");
+
+        if let HeosReply::ChangeEvents(success, attrs) = reply {
+            assert!(success);
+            assert_eq!(attrs.get("enable").expect("Parsing enable failed"), "on");
+        } else {
+            panic!("Wrong reply type");
+        }
+    }
+
+    #[test]
+    fn should_identify_command_under_process_reply() {
+        assert!(HeosReply::is_under_process(test_asset!("command_under_process.json")));
+        assert!(!HeosReply::is_under_process(test_asset!("get_players.json")));
+
+        let reply = HeosReply::parse(test_asset!("command_under_process.json"))
+            .expect("Failed to parse command_under_process.json");
+
+        assert_eq!(reply, HeosReply::UnderProcess("player/get_players".into()));
+    }
+
     #[test]
     fn should_parse_error_reply() {
         let reply = HeosReply::parse(test_asset!("error.json"))
@@ -197,6 +468,34 @@ mod heos_reply_test {
         assert_eq!(attrs.get("shuffle").expect("Parsing shuffle failed"), "on_or_off");
     }
 
+    #[test]
+    fn should_decode_message_values() {
+        let json = gjson::parse(r#"{"heos": {"message": "sid=1028&search=Rock %26 Roll %3D 100%25&un=heos@example.com"}}"#);
+        let message = HeosReply::parse_message(&json, "heos.message");
+
+        assert_eq!(message.get("search").expect("Parsing search failed"), "Rock & Roll = 100%");
+        assert_eq!(message.get("un").expect("Parsing un failed"), "heos@example.com");
+    }
+
+    #[test]
+    fn should_round_trip_command_attributes() {
+        let line = HeosCommand::new()
+            .group("browse")
+            .cmd("play_stream")
+            .attr("pid", "844263156")
+            .attr("url", "http://example.com/stream?id=1&fmt=mp3")
+            .to_string();
+
+        /* Echo the attributes like the device does */
+        let message = line.trim_end().split_once('?').expect("Missing attributes").1;
+        let reply = format!(r#"{{"heos": {{"message": "{}"}}}}"#, message);
+        let json = gjson::parse(&reply);
+        let attrs = HeosReply::parse_message(&json, "heos.message");
+
+        assert_eq!(attrs.get("pid").expect("Parsing pid failed"), "844263156");
+        assert_eq!(attrs.get("url").expect("Parsing url failed"), "http://example.com/stream?id=1&fmt=mp3");
+    }
+
     #[test]
     fn should_parse_generic_payload() {
         let json = gjson::parse(test_asset!("get_now_playing_media.json"));
@@ -215,6 +514,56 @@ mod heos_reply_test {
         assert_eq!(devices[0].base_url, env!("TEST_DEVICE_IP"));
     }
 
+    #[test]
+    fn should_parse_queue_payload() {
+        let json = gjson::parse(test_asset!("get_queue.json"));
+        let items = HeosReply::parse_queue_payload(&json, "payload");
+
+        assert_eq!(items[1], HeosQueueItem {
+            queue_id: 2,
+            song_title: "Sons of the Silent Age".into(),
+            album_title: "Heroes".into(),
+            artist_title: "David Bowie".into(),
+            image_url: "http://example.com/heroes.jpg".into(),
+            media_id: "4712".into(),
+            album_id: "815".into(),
+        });
+    }
+
+    #[test]
+    fn should_parse_browse_payload() {
+        let json = gjson::parse(test_asset!("browse_container.json"));
+        let items = HeosReply::parse_browse_payload(&json, "payload");
+
+        assert_eq!(items.len(), 3);
+
+        match &items[0] {
+            HeosBrowseItem::Container(container) => {
+                assert_eq!(container.container_id, "21$12$1");
+                assert_eq!(container.media_id.as_deref(), Some("21$12$1"));
+                assert!(container.playable);
+            },
+            item => panic!("Expected container, got {:?}", item),
+        }
+
+        match &items[1] {
+            HeosBrowseItem::Container(container) => {
+                assert_eq!(container.media_id, None);
+                assert!(!container.playable);
+            },
+            item => panic!("Expected container, got {:?}", item),
+        }
+
+        match &items[2] {
+            HeosBrowseItem::Media(media) => {
+                assert_eq!(media.media_id, "22$3$4711");
+                assert_eq!(media.artist_title, "David Bowie");
+                assert_eq!(media.media_type, "song");
+            },
+            item => panic!("Expected media item, got {:?}", item),
+        }
+    }
+
     #[test]
     fn should_parse_groups_payload() {
         let json = gjson::parse(test_asset!("get_groups.json"));
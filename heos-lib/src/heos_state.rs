@@ -0,0 +1,293 @@
+///
+/// @package heos-dial
+///
+/// @file HEOS lib
+/// @copyright (c) 2024-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv3.
+/// See the file LICENSE for details.
+///
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use crate::heos_error::{HeosError, Result};
+
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub enum PlayState {
+    Play,
+    Pause,
+    #[default]
+    Stop,
+}
+
+impl Display for PlayState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            PlayState::Play => "play",
+            PlayState::Pause => "pause",
+            PlayState::Stop => "stop",
+        })
+    }
+}
+
+impl FromStr for PlayState {
+    type Err = HeosError;
+
+    fn from_str(state_str: &str) -> Result<Self> {
+        match state_str {
+            "play" => Ok(PlayState::Play),
+            "pause" => Ok(PlayState::Pause),
+            "stop" => Ok(PlayState::Stop),
+            state => Err(HeosError::Parse(format!("Play state `{}` unknown", state))),
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub enum RepeatMode {
+    #[default]
+    Off,
+    OnAll,
+    OnOne,
+}
+
+impl Display for RepeatMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            RepeatMode::Off => "off",
+            RepeatMode::OnAll => "on_all",
+            RepeatMode::OnOne => "on_one",
+        })
+    }
+}
+
+impl FromStr for RepeatMode {
+    type Err = HeosError;
+
+    fn from_str(mode_str: &str) -> Result<Self> {
+        match mode_str {
+            "off" => Ok(RepeatMode::Off),
+            "on_all" => Ok(RepeatMode::OnAll),
+            "on_one" => Ok(RepeatMode::OnOne),
+            mode => Err(HeosError::Parse(format!("Repeat mode `{}` unknown", mode))),
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+pub enum ShuffleMode {
+    #[default]
+    Off,
+    On,
+}
+
+impl Display for ShuffleMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", match self {
+            ShuffleMode::Off => "off",
+            ShuffleMode::On => "on",
+        })
+    }
+}
+
+impl FromStr for ShuffleMode {
+    type Err = HeosError;
+
+    fn from_str(mode_str: &str) -> Result<Self> {
+        match parse_on_off(mode_str)? {
+            true => Ok(ShuffleMode::On),
+            false => Ok(ShuffleMode::Off),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum HeosInput {
+    AuxIn1,
+    AuxIn2,
+    AuxIn3,
+    AuxIn4,
+    AuxSingle,
+    Aux1,
+    Aux2,
+    Aux3,
+    Aux4,
+    Aux5,
+    Aux6,
+    Aux7,
+    LineIn1,
+    LineIn2,
+    LineIn3,
+    LineIn4,
+    CoaxIn1,
+    CoaxIn2,
+    OpticalIn1,
+    OpticalIn2,
+    OpticalIn3,
+    HdmiIn1,
+    HdmiIn2,
+    HdmiIn3,
+    HdmiIn4,
+    HdmiArc1,
+    CableSat,
+    Dvd,
+    Bluray,
+    Game,
+    MediaPlayer,
+    Cd,
+    Tuner,
+    HdRadio,
+    TvAudio,
+    Phono,
+    UsbDac,
+    Analog,
+}
+
+const INPUT_NAMES: [(HeosInput, &str); 38] = [
+    (HeosInput::AuxIn1, "inputs/aux_in_1"),
+    (HeosInput::AuxIn2, "inputs/aux_in_2"),
+    (HeosInput::AuxIn3, "inputs/aux_in_3"),
+    (HeosInput::AuxIn4, "inputs/aux_in_4"),
+    (HeosInput::AuxSingle, "inputs/aux_single"),
+    (HeosInput::Aux1, "inputs/aux1"),
+    (HeosInput::Aux2, "inputs/aux2"),
+    (HeosInput::Aux3, "inputs/aux3"),
+    (HeosInput::Aux4, "inputs/aux4"),
+    (HeosInput::Aux5, "inputs/aux5"),
+    (HeosInput::Aux6, "inputs/aux6"),
+    (HeosInput::Aux7, "inputs/aux7"),
+    (HeosInput::LineIn1, "inputs/line_in_1"),
+    (HeosInput::LineIn2, "inputs/line_in_2"),
+    (HeosInput::LineIn3, "inputs/line_in_3"),
+    (HeosInput::LineIn4, "inputs/line_in_4"),
+    (HeosInput::CoaxIn1, "inputs/coax_in_1"),
+    (HeosInput::CoaxIn2, "inputs/coax_in_2"),
+    (HeosInput::OpticalIn1, "inputs/optical_in_1"),
+    (HeosInput::OpticalIn2, "inputs/optical_in_2"),
+    (HeosInput::OpticalIn3, "inputs/optical_in_3"),
+    (HeosInput::HdmiIn1, "inputs/hdmi_in_1"),
+    (HeosInput::HdmiIn2, "inputs/hdmi_in_2"),
+    (HeosInput::HdmiIn3, "inputs/hdmi_in_3"),
+    (HeosInput::HdmiIn4, "inputs/hdmi_in_4"),
+    (HeosInput::HdmiArc1, "inputs/hdmi_arc_1"),
+    (HeosInput::CableSat, "inputs/cable_sat"),
+    (HeosInput::Dvd, "inputs/dvd"),
+    (HeosInput::Bluray, "inputs/bluray"),
+    (HeosInput::Game, "inputs/game"),
+    (HeosInput::MediaPlayer, "inputs/mediaplayer"),
+    (HeosInput::Cd, "inputs/cd"),
+    (HeosInput::Tuner, "inputs/tuner"),
+    (HeosInput::HdRadio, "inputs/hdradio"),
+    (HeosInput::TvAudio, "inputs/tvaudio"),
+    (HeosInput::Phono, "inputs/phono"),
+    (HeosInput::UsbDac, "inputs/usbdac"),
+    (HeosInput::Analog, "inputs/analog"),
+];
+
+impl HeosInput {
+    pub fn all() -> impl Iterator<Item = HeosInput> {
+        INPUT_NAMES.iter().map(|(input, _)| *input)
+    }
+}
+
+impl Display for HeosInput {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match INPUT_NAMES.iter().find(|(input, _)| input == self) {
+            Some((_, name)) => write!(f, "{}", name),
+            None => write!(f, "{:?}", self),
+        }
+    }
+}
+
+impl FromStr for HeosInput {
+    type Err = HeosError;
+
+    fn from_str(input_str: &str) -> Result<Self> {
+        /* Accept names with and without the common prefix */
+        let name = match input_str.starts_with("inputs/") {
+            true => input_str.to_string(),
+            false => format!("inputs/{}", input_str),
+        };
+
+        INPUT_NAMES.iter()
+            .find(|(_, input_name)| *input_name == name)
+            .map(|(input, _)| *input)
+            .ok_or_else(|| HeosError::Parse(format!("Input `{}` unknown", input_str)))
+    }
+}
+
+pub(crate) fn parse_on_off(value: &str) -> Result<bool> {
+    match value {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        value => Err(HeosError::Parse(format!("Value `{}` is neither on nor off", value))),
+    }
+}
+
+pub(crate) fn format_on_off(value: bool) -> &'static str {
+    if value {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+#[derive(Default, Clone, PartialEq, Debug)]
+pub enum AccountState {
+    #[default]
+    SignedOut,
+    SignedIn {
+        user_name: String,
+    },
+}
+
+impl AccountState {
+    pub(crate) fn from_message(message: &str, user_name: Option<&String>) -> Self {
+        /* Message is either `signed_out` or `signed_in&un=name` */
+        match message.starts_with("signed_in") {
+            true => AccountState::SignedIn {
+                user_name: user_name.cloned().unwrap_or_default(),
+            },
+            false => AccountState::SignedOut,
+        }
+    }
+
+    pub fn is_signed_in(&self) -> bool {
+        matches!(self, AccountState::SignedIn { .. })
+    }
+}
+
+impl Display for AccountState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccountState::SignedOut => write!(f, "signed out"),
+            AccountState::SignedIn { user_name } => write!(f, "signed in as {}", user_name),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ConnectionState {
+    Connected,
+    Reconnecting {
+        attempt: u32,
+    },
+    Closed,
+}
+
+impl ConnectionState {
+    pub fn is_connected(&self) -> bool {
+        matches!(self, ConnectionState::Connected)
+    }
+}
+
+impl Display for ConnectionState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConnectionState::Connected => write!(f, "connected"),
+            ConnectionState::Reconnecting { attempt } => write!(f, "reconnecting (attempt {})", attempt),
+            ConnectionState::Closed => write!(f, "closed"),
+        }
+    }
+}
@@ -0,0 +1,53 @@
+///
+/// @package heos-dial
+///
+/// @file HEOS state tests
+/// @copyright (c) 2024-present Christoph Kappel <christoph@unexist.dev>
+/// @version $Id$
+///
+/// This program can be distributed under the terms of the GNU GPLv3.
+/// See the file LICENSE for details.
+///
+
+#[cfg(test)]
+mod heos_state_test {
+    use crate::heos_error::HeosError;
+    use crate::heos_state::{HeosInput, PlayState, RepeatMode, ShuffleMode};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn should_round_trip_modes() {
+        for state in [PlayState::Play, PlayState::Pause, PlayState::Stop] {
+            assert_eq!(state.to_string().parse::<PlayState>().expect("Failed to parse state"), state);
+        }
+
+        for repeat in [RepeatMode::Off, RepeatMode::OnAll, RepeatMode::OnOne] {
+            assert_eq!(repeat.to_string().parse::<RepeatMode>().expect("Failed to parse repeat"), repeat);
+        }
+
+        for shuffle in [ShuffleMode::Off, ShuffleMode::On] {
+            assert_eq!(shuffle.to_string().parse::<ShuffleMode>().expect("Failed to parse shuffle"), shuffle);
+        }
+    }
+
+    #[test]
+    fn should_round_trip_inputs() {
+        for input in HeosInput::all() {
+            assert_eq!(input.to_string().parse::<HeosInput>().expect("Failed to parse input"), input);
+        }
+    }
+
+    #[test]
+    fn should_parse_input_without_prefix() {
+        let input = "hdmi_arc_1".parse::<HeosInput>()
+            .expect("Failed to parse input");
+
+        assert_eq!(input, HeosInput::HdmiArc1);
+        assert_eq!(input.to_string(), "inputs/hdmi_arc_1");
+    }
+
+    #[test]
+    fn should_reject_unknown_input() {
+        assert!(matches!("inputs/gramophone".parse::<HeosInput>(), Err(HeosError::Parse(_))));
+    }
+}
@@ -11,21 +11,40 @@
 
 mod constants;
 mod macros;
+#[cfg(test)]
+mod heos_mock;
 
 pub mod heos;
 pub mod heos_device;
 pub mod heos_group;
 pub mod heos_command;
 pub mod heos_reply;
+pub mod heos_event;
+pub mod heos_connection;
+pub mod heos_state;
+pub mod heos_error;
+pub mod heos_queue;
+pub mod heos_browse;
 
 mod heos_test;
 mod heos_device_test;
 mod heos_group_test;
 mod heos_command_test;
 mod heos_reply_test;
+mod heos_event_test;
+mod heos_connection_test;
+mod heos_error_test;
+mod heos_state_test;
 mod heos_media;
 
 pub use heos::Heos;
 pub use heos_device::HeosDevice;
 pub use heos_group::HeosGroup;
 pub use heos_reply::HeosReply;
+pub use heos_event::HeosEvent;
+pub use heos_connection::{HeosConnection, HeosConnectionConfig};
+pub use heos_state::{AccountState, ConnectionState, HeosInput, PlayState, RepeatMode, ShuffleMode};
+pub use heos_error::{HeosError, HeosErrorCode, HeosSystemError};
+pub use heos_queue::HeosQueueItem;
+pub use heos_media::{HeosMedia, HeosMediaSourceType, HeosMediaType, HeosProgress};
+pub use heos_browse::{AddCriteria, HeosBrowseItem, HeosBrowseResult, HeosContainer, HeosMediaItem, HeosSearchCriteria, HeosSource, ServiceOption, ServiceOptionTarget};
@@ -10,39 +10,85 @@
 ///
 
 use std::{error, fmt};
-use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
-use heos_lib::{HeosDevice, HeosGroup, HeosReply};
+use heos_lib::{AccountState, AddCriteria, HeosBrowseItem, HeosDevice, HeosError, HeosGroup, HeosInput, HeosMediaItem, HeosSearchCriteria, HeosQueueItem, PlayState, RepeatMode, ServiceOption, ServiceOptionTarget, ShuffleMode};
 use ratatui::widgets::ListState;
 use std::sync::{Arc, RwLock};
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use heos_lib::heos_command::{HeosCommand, HeosCommandHandler};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use log::{error, info};
 use tokio::sync::mpsc;
 use crate::events::Event;
 
 pub type AppResult<T> = Result<T, Box<dyn error::Error>>;
 
-#[derive(Debug)]
-pub(crate) enum PlayerState {
-    Play,
-    Stop,
-}
-
-impl Display for PlayerState {
-    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        write!(f, "{:?}", self)
-    }
-}
-
 #[derive(Debug, Default)]
 pub(crate) enum Focus {
     #[default]
     Devices,
 
     Groups,
+
+    Queue,
+
+    Browser,
 }
 
+#[derive(Debug, Default)]
+pub(crate) struct Queue {
+    pub(crate) player_id: String,
+    pub(crate) items: Vec<HeosQueueItem>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct BrowseLevel {
+    pub(crate) name: String,
+    pub(crate) source_id: Option<String>,
+    pub(crate) container_id: Option<String>,
+    pub(crate) items: Vec<HeosBrowseItem>,
+    pub(crate) count: u32,
+    pub(crate) is_loading: bool,
+    pub(crate) search: Option<BrowseSearch>,
+    parent_selected: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct BrowseSearch {
+    pub(crate) criteria_id: String,
+    pub(crate) query: String,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct SearchPrompt {
+    pub(crate) source_id: String,
+    pub(crate) source_name: String,
+    pub(crate) criteria: Vec<HeosSearchCriteria>,
+    pub(crate) selected: usize,
+    pub(crate) query: String,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct OptionsMenu {
+    pub(crate) player_id: String,
+    pub(crate) options: Vec<ServiceOption>,
+    pub(crate) selected: usize,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct SignInPrompt {
+    pub(crate) user_name: String,
+    pub(crate) password: String,
+    pub(crate) password_focused: bool,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Browser {
+    pub(crate) levels: Vec<BrowseLevel>,
+}
+
+const QUEUE_PAGE_SIZE: u32 = 100;
+const BROWSE_PAGE_SIZE: u32 = 50;
+
 impl Display for Focus {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         write!(f, "{:?}", self)
@@ -55,6 +101,18 @@ pub struct App {
     pub(crate) group_list: Arc<RwLock<Vec<HeosGroup>>>,
     pub(crate) dev_list_state: ListState,
     pub(crate) group_list_state: ListState,
+    pub(crate) queue: Arc<RwLock<Queue>>,
+    pub(crate) queue_list_state: ListState,
+    pub(crate) browser: Arc<RwLock<Browser>>,
+    pub(crate) browser_list_state: ListState,
+    pub(crate) search_prompt: Arc<RwLock<Option<SearchPrompt>>>,
+    pub(crate) favorites: Arc<RwLock<Vec<HeosMediaItem>>>,
+    pub(crate) palette: Option<String>,
+    pub(crate) options_menu: Option<OptionsMenu>,
+    pub(crate) account: Arc<RwLock<AccountState>>,
+    pub(crate) sign_in_prompt: Option<SignInPrompt>,
+    pub(crate) marked: Vec<String>,
+    pub(crate) show_help: bool,
     pub(crate) focus_state: Focus,
     pub is_running: bool,
     sender: mpsc::UnboundedSender<Event>,
@@ -63,6 +121,9 @@ pub struct App {
 impl App {
     pub(crate) fn new(dev_list: Arc<RwLock<Vec<HeosDevice>>>,
                       group_list: Arc<RwLock<Vec<HeosGroup>>>,
+                      queue: Arc<RwLock<Queue>>,
+                      favorites: Arc<RwLock<Vec<HeosMediaItem>>>,
+                      account: Arc<RwLock<AccountState>>,
                       sender: mpsc::UnboundedSender<Event>) -> App {
         Self {
             is_running: true,
@@ -70,12 +131,56 @@ impl App {
             group_list,
             dev_list_state: ListState::default(),
             group_list_state: ListState::default(),
+            queue,
+            queue_list_state: ListState::default(),
+            browser: Arc::new(RwLock::new(Browser::default())),
+            browser_list_state: ListState::default(),
+            search_prompt: Arc::new(RwLock::new(None)),
+            favorites,
+            palette: None,
+            options_menu: None,
+            account,
+            sign_in_prompt: None,
+            marked: Vec::new(),
+            show_help: false,
             focus_state: Focus::default(),
             sender,
         }
     }
 
     pub fn handle_key_events(&mut self, key_event: KeyEvent) -> AppResult<()> {
+        /* Prompt swallows all keys while open */
+        if self.search_prompt.read().unwrap().is_some() {
+            self.handle_search_keys(key_event);
+
+            return Ok(());
+        }
+
+        /* Any key closes the help */
+        if self.show_help {
+            self.show_help = false;
+
+            return Ok(());
+        }
+
+        if self.palette.is_some() {
+            self.handle_palette_keys(key_event);
+
+            return Ok(());
+        }
+
+        if self.options_menu.is_some() {
+            self.handle_options_keys(key_event);
+
+            return Ok(());
+        }
+
+        if self.sign_in_prompt.is_some() {
+            self.handle_sign_in_keys(key_event);
+
+            return Ok(());
+        }
+
         match key_event.code {
             /* Navigation */
             KeyCode::Char('h') | KeyCode::Left => self.set_volume(-1),
@@ -90,12 +195,54 @@ impl App {
             /* List selection */
             KeyCode::Char('d') => self.select_list(Focus::Devices),
             KeyCode::Char('g') => self.select_list(Focus::Groups),
+            KeyCode::Char('u') => self.select_list(Focus::Queue),
+            KeyCode::Char('b') => self.select_list(Focus::Browser),
+
+            KeyCode::Enter => match self.focus_state {
+                Focus::Queue => self.play_queue_item(),
+                Focus::Browser => self.open_browse_item(),
+                _ => {},
+            },
+
+            /* Browser */
+            KeyCode::Char('/') => self.open_search(),
+            KeyCode::Backspace => self.close_browse_level(),
+            KeyCode::Char('P') => self.add_browse_item(AddCriteria::PlayNow),
+            KeyCode::Char('n') => self.add_browse_item(AddCriteria::PlayNext),
+            KeyCode::Char('a') => self.add_browse_item(AddCriteria::AddToEnd),
+            KeyCode::Char('R') => self.add_browse_item(AddCriteria::ReplaceAndPlay),
+
+            /* Queue */
+            KeyCode::Char('x') | KeyCode::Delete => self.remove_queue_item(),
+            KeyCode::Char('K') => self.move_queue_item(-1),
+            KeyCode::Char('J') => self.move_queue_item(1),
+            KeyCode::Char('X') => self.clear_queue(),
+
+            /* Grouping */
+            KeyCode::Char(' ') => self.toggle_mark(),
+            KeyCode::Char('G') => self.apply_group(),
+            KeyCode::Char('U') => self.ungroup(),
+
+            /* Command palette */
+            KeyCode::Char(':') => self.palette = Some(String::new()),
+
+            /* Presets */
+            KeyCode::Char(c @ '1'..='9') => self.play_preset(c as u8 - b'0'),
 
             /* Player */
-            KeyCode::Char('p') => self.set_play_state(PlayerState::Play),
-            KeyCode::Char('s') => self.set_play_state(PlayerState::Stop),
+            KeyCode::Char('p') => self.set_play_state(PlayState::Play),
+            KeyCode::Char('s') => self.set_play_state(PlayState::Stop),
 
             KeyCode::Char('m') => self.toggle_mute(),
+            KeyCode::Char('r') => self.cycle_repeat(),
+            KeyCode::Char('z') => self.toggle_shuffle(),
+            KeyCode::Char('o') => self.open_options(),
+
+            /* Account */
+            KeyCode::Char('A') => self.toggle_account(),
+
+            /* Help */
+            KeyCode::Char('?') => self.show_help = true,
 
             /* Exit keys */
             KeyCode::Char('q') => self.quit(),
@@ -118,6 +265,15 @@ impl App {
         match self.focus_state {
             Focus::Devices => self.group_list_state.select(None),
             Focus::Groups => self.dev_list_state.select(None),
+            Focus::Queue | Focus::Browser => {},
+        }
+    }
+
+    fn load_selected(&mut self) {
+        match self.focus_state {
+            Focus::Devices => self.load_queue(),
+            Focus::Browser => self.load_more_browse_items(),
+            Focus::Groups | Focus::Queue => {},
         }
     }
 
@@ -125,61 +281,137 @@ impl App {
         match self.focus_state {
             Focus::Devices => self.dev_list_state.select(None),
             Focus::Groups => self.group_list_state.select(None),
+            Focus::Queue => self.queue_list_state.select(None),
+            Focus::Browser => self.browser_list_state.select(None),
         }
-        self.deselect_all()
+        self.deselect_all();
+        self.load_selected()
     }
 
     fn select_next(&mut self) {
         match self.focus_state {
             Focus::Devices => self.dev_list_state.select_next(),
             Focus::Groups => self.group_list_state.select_next(),
+            Focus::Queue => self.queue_list_state.select_next(),
+            Focus::Browser => self.browser_list_state.select_next(),
         }
-        self.deselect_all()
+        self.deselect_all();
+        self.load_selected()
     }
 
     fn select_previous(&mut self) {
         match self.focus_state {
             Focus::Devices => self.dev_list_state.select_previous(),
             Focus::Groups => self.group_list_state.select_previous(),
+            Focus::Queue => self.queue_list_state.select_previous(),
+            Focus::Browser => self.browser_list_state.select_previous(),
         }
-        self.deselect_all()
+        self.deselect_all();
+        self.load_selected()
     }
 
     fn select_first(&mut self) {
         match self.focus_state {
             Focus::Devices => self.dev_list_state.select_first(),
             Focus::Groups => self.group_list_state.select_first(),
+            Focus::Queue => self.queue_list_state.select_first(),
+            Focus::Browser => self.browser_list_state.select_first(),
         }
-        self.deselect_all()
+        self.deselect_all();
+        self.load_selected()
     }
 
     fn select_last(&mut self) {
         match self.focus_state {
             Focus::Devices => self.dev_list_state.select_last(),
             Focus::Groups => self.group_list_state.select_last(),
+            Focus::Queue => self.queue_list_state.select_last(),
+            Focus::Browser => self.browser_list_state.select_last(),
         }
-        self.deselect_all()
+        self.deselect_all();
+        self.load_selected()
     }
 
     fn select_list(&mut self, focus_state: Focus) {
         self.focus_state = focus_state;
+
+        /* Start at the music sources when opened for the first time */
+        if matches!(self.focus_state, Focus::Browser) {
+            let mut write_browser = self.browser.write().unwrap();
+
+            if write_browser.levels.is_empty() {
+                write_browser.levels.push(BrowseLevel {
+                    name: "Music Sources".into(),
+                    is_loading: true,
+                    ..Default::default()
+                });
+
+                drop(write_browser);
+
+                self.load_browse_page(0, 0);
+            }
+        }
     }
 
-    fn set_volume(&mut self, step: i16) {
+    fn set_volume(&mut self, step: i8) {
         match self.focus_state {
-            Focus::Devices => self.set_player_volume(step),
+            Focus::Devices | Focus::Queue | Focus::Browser => self.set_player_volume(step),
             Focus::Groups => self.set_group_volume(step),
         }
     }
 
     fn toggle_mute(&mut self) {
         match self.focus_state {
-            Focus::Devices => self.toggle_player_mute(),
+            Focus::Devices | Focus::Queue | Focus::Browser => self.toggle_player_mute(),
             Focus::Groups => self.toggle_group_mute(),
         }
     }
 
-    fn set_player_volume(&mut self, step: i16) {
+    fn set_player_volume(&mut self, step: i8) {
+        if let Some(i) = self.dev_list_state.selected() {
+            let mut dev = self.dev_list.read().unwrap().get(i).unwrap().clone();
+
+            tokio::spawn(async move {
+                info!("set_player_volume: step={}", step);
+
+                /* Let the device do the math to not race with other controllers */
+                let res = if 0 > step {
+                    dev.volume_down(step.unsigned_abs()).await
+                } else {
+                    dev.volume_up(step.unsigned_abs()).await
+                };
+
+                /* Volume changed events update the list */
+                if let Err(err) = res {
+                    error!("set_player_volume: {:?}", err);
+                }
+            });
+        }
+    }
+
+    fn set_group_volume(&mut self, step: i8) {
+        if let Some(i) = self.group_list_state.selected() {
+            let mut group = self.group_list.read().unwrap().get(i).unwrap().clone();
+
+            tokio::spawn(async move {
+                info!("set_group_volume: step={}", step);
+
+                /* Let the device do the math to not race with other controllers */
+                let res = if 0 > step {
+                    group.volume_down(step.unsigned_abs()).await
+                } else {
+                    group.volume_up(step.unsigned_abs()).await
+                };
+
+                /* Volume changed events update the list */
+                if let Err(err) = res {
+                    error!("set_group_volume: {:?}", err);
+                }
+            });
+        }
+    }
+
+    fn set_play_state(&mut self, state: PlayState) {
         if let Some(i) = self.dev_list_state.selected() {
             let dev_list = Arc::clone(&self.dev_list);
             let read_list = dev_list.read().unwrap();
@@ -191,107 +423,174 @@ impl App {
             let cloned_sender = self.sender.clone();
 
             tokio::spawn(async move {
+                info!("set_play_state: state={}", state);
 
-                /* Calculate and normalize new volume level */
-                let new_level = dev.volume as i16 + step;
-
-                let level : u16 = if 0 > new_level {
-                    0
-                } else {
-                    new_level as u16
-                };
+                match dev.set_play_state(state).await {
+                    Ok(_) => cloned_sender.send(Event::Redraw).unwrap(),
+                    Err(err) => error!("set_play_state: {:?}", err),
+                }
+            });
+        }
+    }
 
-                let level_str = level.to_string();
+    fn toggle_mark(&mut self) {
+        if !matches!(self.focus_state, Focus::Devices) {
+            return;
+        }
 
-                info!("set_player_volume: level={}", level_str);
+        if let Some(dev) = self.selected_device() {
+            match self.marked.iter().position(|player_id| *player_id == dev.player_id) {
+                Some(i) => {
+                    self.marked.remove(i);
+                },
+                None => self.marked.push(dev.player_id),
+            }
+        }
+    }
 
-                let cmd = HeosCommand::new()
-                    .group("player")
-                    .cmd("set_volume")
-                    .attr("level", &*level_str);
+    fn apply_group(&mut self) {
+        let marked = std::mem::take(&mut self.marked);
+        let cloned_sender = self.sender.clone();
 
-                let reply = dev.send_command(&cmd).await.unwrap_or_else(|err| {
-                    HeosReply::Error(false, err.to_string(), HashMap::default())
-                });
+        /* Either extend the selected group or form a new one */
+        if let Some(i) = self.group_list_state.selected() {
+            if marked.is_empty() {
+                error!("apply_group: Mark devices with space first");
 
-                if let HeosReply::Volume(success, _) = reply {
-                    info!("set_player_volume: success={}, level={}", success, level);
+                return;
+            }
 
-                    if success {
-                        let mut write_list = dev_list.write().unwrap();
+            let mut group = self.group_list.read().unwrap().get(i).unwrap().clone();
 
-                        #[allow(unused_mut)]
-                        let mut dev = write_list.get_mut(i).unwrap();
+            tokio::spawn(async move {
+                let member_ids: Vec<&str> = marked.iter()
+                    .map(String::as_str)
+                    .collect();
 
-                        dev.volume = level;
+                info!("apply_group: group={}, members={:?}", group, member_ids);
 
-                        cloned_sender.send(Event::Redraw).unwrap();
-                    }
-                } else if let HeosReply::Error(success, command, message) = reply {
-                    error!("set_player_volume: success={}, command={:?}, message={:?}",
-                        success, command, message);
+                match group.add_members(&member_ids).await {
+                    Ok(_) => cloned_sender.send(Event::Redraw).unwrap(),
+                    Err(err) => error!("apply_group: {:?}", err),
                 }
             });
-        }
-    }
+        } else {
+            let maybe_leader = self.selected_device().or_else(|| {
+                marked.first().and_then(|leader_id| self.dev_list.read().unwrap().iter()
+                    .find(|dev| dev.player_id == *leader_id).cloned())
+            });
 
-    fn set_group_volume(&mut self, step: i16) {
-        if let Some(i) = self.group_list_state.selected() {
-            let group_list = Arc::clone(&self.group_list);
-            let read_list = group_list.read().unwrap();
+            let mut leader = match maybe_leader {
+                Some(leader) => leader,
+                None => {
+                    error!("apply_group: Mark devices with space first");
 
-            let mut group = read_list.get(i).unwrap().clone();
+                    return;
+                }
+            };
 
-            drop(read_list);
+            let member_ids: Vec<String> = marked.into_iter()
+                .filter(|player_id| *player_id != leader.player_id)
+                .collect();
 
-            let cloned_sender = self.sender.clone();
+            /* Just the leader would dissolve its current group */
+            if member_ids.is_empty() {
+                error!("apply_group: Mark devices with space first");
+
+                return;
+            }
 
             tokio::spawn(async move {
+                let member_ids: Vec<&str> = member_ids.iter()
+                    .map(String::as_str)
+                    .collect();
 
-                /* Calculate and normalize new volume level */
-                let new_level = group.volume as i16 + step;
+                info!("apply_group: leader={}, members={:?}", leader, member_ids);
 
-                let level : u16 = if 0 > new_level {
-                    0
-                } else {
-                    new_level as u16
-                };
+                match leader.set_group(&member_ids).await {
+                    Ok(Some(group)) => {
+                        info!("apply_group: Formed group {}", group);
 
-                let level_str = level.to_string();
+                        cloned_sender.send(Event::Redraw).unwrap();
+                    },
+                    Ok(None) => cloned_sender.send(Event::Redraw).unwrap(),
+                    Err(err) => error!("apply_group: {:?}", err),
+                }
+            });
+        }
+    }
 
-                info!("set_group_volume: level={}", level_str);
+    fn ungroup(&mut self) {
+        let mut group = match self.group_list_state.selected()
+            .and_then(|i| self.group_list.read().unwrap().get(i).cloned())
+        {
+            Some(group) => group,
+            None => {
+                error!("ungroup: No group selected");
 
-                let cmd = HeosCommand::new()
-                    .group("group")
-                    .cmd("set_volume")
-                    .attr("level", &*level_str);
+                return;
+            }
+        };
 
-                let reply = group.send_command(&cmd).await.unwrap_or_else(|err| {
-                    HeosReply::Error(false, err.to_string(), HashMap::default())
-                });
+        let cloned_sender = self.sender.clone();
 
-                if let HeosReply::Volume(success, _) = reply {
-                    info!("set_group_volume: success={}, level={}", success, level);
+        tokio::spawn(async move {
+            info!("ungroup: group={}", group);
 
-                    if success {
-                        let mut write_list = group_list.write().unwrap();
+            match group.ungroup().await {
+                Ok(_) => cloned_sender.send(Event::Redraw).unwrap(),
+                Err(err) => error!("ungroup: {:?}", err),
+            }
+        });
+    }
 
-                        #[allow(unused_mut)]
-                        let mut group = write_list.get_mut(i).unwrap();
+    fn play_preset(&mut self, preset: u8) {
+        let cloned_sender = self.sender.clone();
 
-                        group.volume = level;
+        /* Groups take precedence, presets are played by their leader */
+        if let Some(i) = self.group_list_state.selected() {
+            let mut group = self.group_list.read().unwrap().get(i).unwrap().clone();
 
-                        cloned_sender.send(Event::Redraw).unwrap();
-                    }
-                } else if let HeosReply::Error(success, command, message) = reply {
-                    error!("set_group_volume: success={}, command={:?}, message={:?}",
-                        success, command, message);
+            tokio::spawn(async move {
+                info!("play_preset: preset={}, group={}", preset, group);
+
+                match group.play_preset(preset).await {
+                    Ok(_) => cloned_sender.send(Event::Redraw).unwrap(),
+                    Err(err) => error!("play_preset: {:?}", err),
+                }
+            });
+        } else if let Some(mut dev) = self.selected_device() {
+            tokio::spawn(async move {
+                info!("play_preset: preset={}, dev={}", preset, dev);
+
+                match dev.play_preset(preset).await {
+                    Ok(_) => cloned_sender.send(Event::Redraw).unwrap(),
+                    Err(err) => error!("play_preset: {:?}", err),
                 }
             });
         }
     }
 
-    fn set_play_state(&mut self, state: PlayerState) {
+    fn cycle_repeat(&mut self) {
+        self.set_play_mode(|repeat, shuffle| {
+            (match repeat {
+                RepeatMode::Off => RepeatMode::OnAll,
+                RepeatMode::OnAll => RepeatMode::OnOne,
+                RepeatMode::OnOne => RepeatMode::Off,
+            }, shuffle)
+        });
+    }
+
+    fn toggle_shuffle(&mut self) {
+        self.set_play_mode(|repeat, shuffle| {
+            (repeat, match shuffle {
+                ShuffleMode::Off => ShuffleMode::On,
+                ShuffleMode::On => ShuffleMode::Off,
+            })
+        });
+    }
+
+    fn set_play_mode(&mut self, next_mode: fn(RepeatMode, ShuffleMode) -> (RepeatMode, ShuffleMode)) {
         if let Some(i) = self.dev_list_state.selected() {
             let dev_list = Arc::clone(&self.dev_list);
             let read_list = dev_list.read().unwrap();
@@ -303,24 +602,22 @@ impl App {
             let cloned_sender = self.sender.clone();
 
             tokio::spawn(async move {
-                let state_str = state.to_string().to_lowercase();
+                let (repeat, shuffle) = next_mode(dev.repeat, dev.shuffle);
 
-                info!("set_play_state: state={}", state_str);
+                info!("set_play_mode: repeat={}, shuffle={}", repeat, shuffle);
 
-                let cmd = HeosCommand::new()
-                    .group("player")
-                    .cmd("set_play_state")
-                    .attr("state", &*state_str);
-
-                let reply = dev.send_command(&cmd).await.unwrap();
+                match dev.set_play_mode(repeat, shuffle).await {
+                    Ok(_) => {
+                        let mut write_list = dev_list.write().unwrap();
 
-                if let HeosReply::PlayState(success, _) = reply {
-                    info!("set_play_state: success={}, state={}", success, state_str);
+                        if let Some(listed_dev) = write_list.get_mut(i) {
+                            listed_dev.repeat = dev.repeat;
+                            listed_dev.shuffle = dev.shuffle;
+                        }
 
-                    cloned_sender.send(Event::Redraw).unwrap();
-                } else if let HeosReply::Error(success, command, message) = reply {
-                    error!("set_play_state: success={}, command={:?}, message={:?}",
-                        success, command, message);
+                        cloned_sender.send(Event::Redraw).unwrap();
+                    },
+                    Err(err) => error!("set_play_mode: {:?}", err),
                 }
             });
         }
@@ -338,21 +635,19 @@ impl App {
             let cloned_sender = self.sender.clone();
 
             tokio::spawn(async move {
-                info!("toggle_mute");
-
-                let cmd = HeosCommand::new()
-                    .group("player")
-                    .cmd("toggle_mute");
+                info!("toggle_player_mute");
 
-                let reply = dev.send_command(&cmd).await.unwrap();
+                match dev.toggle_mute().await {
+                    Ok(_) => {
+                        let mut write_list = dev_list.write().unwrap();
 
-                if let HeosReply::Mute(success, _) = reply {
-                    info!("toggle_mute: success={}", success);
+                        if let Some(listed) = write_list.get_mut(i) {
+                            listed.muted = dev.muted;
+                        }
 
-                    cloned_sender.send(Event::Redraw).unwrap();
-                } else if let HeosReply::Error(success, command, message) = reply {
-                    error!("toggle_player_mute: success={}, command={:?}, message={:?}",
-                        success, command, message);
+                        cloned_sender.send(Event::Redraw).unwrap();
+                    },
+                    Err(err) => error!("toggle_player_mute: {:?}", err),
                 }
             });
         }
@@ -370,23 +665,770 @@ impl App {
             let cloned_sender = self.sender.clone();
 
             tokio::spawn(async move {
-                info!("toggle_mute");
+                info!("toggle_group_mute");
+
+                match group.toggle_mute().await {
+                    Ok(_) => {
+                        let mut write_list = group_list.write().unwrap();
+
+                        if let Some(listed) = write_list.get_mut(i) {
+                            listed.muted = group.muted;
+                        }
+
+                        cloned_sender.send(Event::Redraw).unwrap();
+                    },
+                    Err(err) => error!("toggle_group_mute: {:?}", err),
+                }
+            });
+        }
+    }
+
+    fn selected_device(&self) -> Option<HeosDevice> {
+        let read_list = self.dev_list.read().unwrap();
+
+        /* Selection is only clamped on render, select_last() sets it to usize::MAX */
+        self.dev_list_state.selected()
+            .and_then(|i| read_list.get(i.min(read_list.len().saturating_sub(1))).cloned())
+    }
+
+    fn browse_device(&self) -> Option<HeosDevice> {
+        self.selected_device()
+            .or_else(|| self.dev_list.read().unwrap().first().cloned())
+    }
+
+    fn selected_browse_item(&self) -> Option<(Option<String>, Option<String>, HeosBrowseItem)> {
+        if !matches!(self.focus_state, Focus::Browser) {
+            return None;
+        }
+
+        let i = self.browser_list_state.selected()?;
+        let read_browser = self.browser.read().unwrap();
+        let level = read_browser.levels.last()?;
+
+        level.items.get(i).map(|item| {
+            (level.source_id.clone(), level.container_id.clone(), item.clone())
+        })
+    }
+
+    fn open_browse_item(&mut self) {
+        let (source_id, _, item) = match self.selected_browse_item() {
+            Some(selected) => selected,
+            None => return,
+        };
+
+        let level = match item {
+            HeosBrowseItem::Source(source) => BrowseLevel {
+                name: source.name,
+                source_id: Some(source.source_id),
+                ..Default::default()
+            },
+            HeosBrowseItem::Container(container) => BrowseLevel {
+                name: container.name,
+                source_id,
+                container_id: Some(container.container_id),
+                ..Default::default()
+            },
+            HeosBrowseItem::Media(_) => return self.add_browse_item(AddCriteria::PlayNow),
+        };
+
+        let mut write_browser = self.browser.write().unwrap();
+
+        write_browser.levels.push(BrowseLevel {
+            is_loading: true,
+            parent_selected: self.browser_list_state.selected(),
+            ..level
+        });
+
+        let depth = write_browser.levels.len() - 1;
+
+        drop(write_browser);
+
+        self.browser_list_state.select(None);
+        self.load_browse_page(depth, 0);
+    }
+
+    fn close_browse_level(&mut self) {
+        if !matches!(self.focus_state, Focus::Browser) {
+            return;
+        }
+
+        let mut write_browser = self.browser.write().unwrap();
+
+        /* Keep the music sources */
+        if 1 < write_browser.levels.len() {
+            if let Some(level) = write_browser.levels.pop() {
+                self.browser_list_state.select(level.parent_selected);
+            }
+        }
+    }
+
+    fn load_more_browse_items(&mut self) {
+        let selected = match self.browser_list_state.selected() {
+            Some(i) => i,
+            None => return,
+        };
+
+        let mut write_browser = self.browser.write().unwrap();
+        let depth = write_browser.levels.len().saturating_sub(1);
+
+        if let Some(level) = write_browser.levels.last_mut() {
+            /* Selection is only clamped on render, select_last() sets it to usize::MAX */
+            let selected = selected.min(level.items.len().saturating_sub(1));
+
+            /* Fetch the next page once the selection reaches the end */
+            if !level.is_loading && selected + 1 >= level.items.len()
+                && (level.items.len() as u32) < level.count
+            {
+                level.is_loading = true;
+
+                let start = level.items.len() as u32;
+
+                drop(write_browser);
+
+                self.load_browse_page(depth, start);
+            }
+        }
+    }
+
+    fn load_browse_page(&self, depth: usize, start: u32) {
+        match self.browse_device() {
+            Some(dev) => {
+                tokio::spawn(fetch_browse_page(dev, Arc::clone(&self.browser),
+                                               depth, start, self.sender.clone()));
+            },
+            None => {
+                error!("load_browse_page: No device found");
+
+                if let Some(level) = self.browser.write().unwrap().levels.get_mut(depth) {
+                    level.is_loading = false;
+                }
+            }
+        }
+    }
+
+    fn add_browse_item(&mut self, criteria: AddCriteria) {
+        let (source_id, container_id, item) = match self.selected_browse_item() {
+            Some((Some(source_id), container_id, item)) => (source_id, container_id, item),
+            _ => return,
+        };
+
+        let mut dev = match self.selected_device() {
+            Some(dev) => dev,
+            None => {
+                error!("add_browse_item: No device selected");
+
+                return;
+            }
+        };
+
+        tokio::spawn(async move {
+            info!("add_browse_item: item={}, criteria={:?}", item, criteria);
+
+            let res = match item {
+                HeosBrowseItem::Container(container) => dev.add_to_queue(
+                    &source_id, &container.container_id, None, criteria).await,
+                HeosBrowseItem::Media(media) => match container_id {
+                    Some(container_id) => dev.add_to_queue(
+                        &source_id, &container_id, Some(&media.media_id), criteria).await,
+                    None => Err(HeosError::InvalidArgument("Container id missing".into())),
+                },
+                HeosBrowseItem::Source(_) => return,
+            };
+
+            if let Err(err) = res {
+                error!("add_browse_item: {:?}", err);
+            }
+        });
+    }
+
+    fn handle_search_keys(&mut self, key_event: KeyEvent) {
+        let mut write_prompt = self.search_prompt.write().unwrap();
+
+        let prompt = match write_prompt.as_mut() {
+            Some(prompt) => prompt,
+            None => return,
+        };
+
+        match key_event.code {
+            KeyCode::Char('c') if key_event.modifiers == KeyModifiers::CONTROL => *write_prompt = None,
+            KeyCode::Char(c) => prompt.query.push(c),
+            KeyCode::Backspace => {
+                prompt.query.pop();
+            },
+            KeyCode::Tab if !prompt.criteria.is_empty() => {
+                prompt.selected = (prompt.selected + 1) % prompt.criteria.len();
+            },
+            KeyCode::Esc => *write_prompt = None,
+            KeyCode::Enter => {
+                if let Some(prompt) = write_prompt.take() {
+                    drop(write_prompt);
+
+                    self.start_search(prompt);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    fn handle_palette_keys(&mut self, key_event: KeyEvent) {
+        let palette = match self.palette.as_mut() {
+            Some(palette) => palette,
+            None => return,
+        };
+
+        match key_event.code {
+            KeyCode::Char('c') if key_event.modifiers == KeyModifiers::CONTROL => self.palette = None,
+            KeyCode::Char(c) => palette.push(c),
+            KeyCode::Backspace => {
+                palette.pop();
+            },
+            KeyCode::Esc => self.palette = None,
+            KeyCode::Enter => {
+                if let Some(line) = self.palette.take() {
+                    self.run_command(&line);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    fn handle_options_keys(&mut self, key_event: KeyEvent) {
+        let menu = match self.options_menu.as_mut() {
+            Some(menu) => menu,
+            None => return,
+        };
+
+        match key_event.code {
+            KeyCode::Char('c') if key_event.modifiers == KeyModifiers::CONTROL => self.options_menu = None,
+            KeyCode::Char('j') | KeyCode::Down => {
+                menu.selected = (menu.selected + 1) % menu.options.len();
+            },
+            KeyCode::Char('k') | KeyCode::Up => {
+                menu.selected = (menu.selected + menu.options.len() - 1) % menu.options.len();
+            },
+            KeyCode::Esc | KeyCode::Char('o') => self.options_menu = None,
+            KeyCode::Enter => {
+                if let Some(menu) = self.options_menu.take() {
+                    self.set_service_option(menu);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    fn open_options(&mut self) {
+        let dev = match self.selected_device() {
+            Some(dev) => dev,
+            None => {
+                error!("open_options: No device selected");
+
+                return;
+            }
+        };
+
+        match dev.media {
+            Some(media) if !media.options.is_empty() => {
+                self.options_menu = Some(OptionsMenu {
+                    player_id: dev.player_id,
+                    options: media.options,
+                    selected: 0,
+                });
+            },
+            _ => error!("open_options: No options for the current media of {}", dev),
+        }
+    }
+
+    fn set_service_option(&mut self, menu: OptionsMenu) {
+        let option = match menu.options.get(menu.selected) {
+            Some(option) => *option,
+            None => return,
+        };
+
+        let maybe_dev = self.dev_list.read().unwrap().iter()
+            .find(|dev| dev.player_id == menu.player_id).cloned();
+
+        if let Some(mut dev) = maybe_dev {
+            let cloned_sender = self.sender.clone();
+
+            tokio::spawn(async move {
+                info!("set_service_option: option={}, dev={}", option, dev);
+
+                match dev.set_service_option(option, ServiceOptionTarget::NowPlaying).await {
+                    Ok(_) => cloned_sender.send(Event::Redraw).unwrap(),
+                    Err(err) => error!("set_service_option: {:?}", err),
+                }
+            });
+        }
+    }
+
+    fn handle_sign_in_keys(&mut self, key_event: KeyEvent) {
+        let prompt = match self.sign_in_prompt.as_mut() {
+            Some(prompt) => prompt,
+            None => return,
+        };
+
+        let field = match prompt.password_focused {
+            true => &mut prompt.password,
+            false => &mut prompt.user_name,
+        };
+
+        match key_event.code {
+            KeyCode::Char('c') if key_event.modifiers == KeyModifiers::CONTROL => self.sign_in_prompt = None,
+            KeyCode::Char(c) => field.push(c),
+            KeyCode::Backspace => {
+                field.pop();
+            },
+            KeyCode::Tab => prompt.password_focused = !prompt.password_focused,
+            KeyCode::Esc => self.sign_in_prompt = None,
+            KeyCode::Enter => {
+                if let Some(prompt) = self.sign_in_prompt.take() {
+                    self.sign_in(prompt);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    fn toggle_account(&mut self) {
+        if !self.account.read().unwrap().is_signed_in() {
+            self.sign_in_prompt = Some(SignInPrompt::default());
+
+            return;
+        }
+
+        let mut dev = match self.dev_list.read().unwrap().first().cloned() {
+            Some(dev) => dev,
+            None => return,
+        };
+
+        let account = Arc::clone(&self.account);
+        let cloned_sender = self.sender.clone();
+
+        tokio::spawn(async move {
+            info!("sign_out");
+
+            match dev.sign_out().await {
+                Ok(state) => {
+                    *account.write().unwrap() = state;
+
+                    cloned_sender.send(Event::Redraw).unwrap();
+                },
+                Err(err) => error!("sign_out: {:?}", err),
+            }
+        });
+    }
+
+    fn sign_in(&mut self, prompt: SignInPrompt) {
+        /* Any device can handle system commands */
+        let mut dev = match self.dev_list.read().unwrap().first().cloned() {
+            Some(dev) => dev,
+            None => {
+                error!("sign_in: No device found");
+
+                return;
+            }
+        };
+
+        let account = Arc::clone(&self.account);
+        let cloned_sender = self.sender.clone();
+
+        tokio::spawn(async move {
+            info!("sign_in: user={}", prompt.user_name);
+
+            match dev.sign_in(&prompt.user_name, &prompt.password).await {
+                Ok(state) => {
+                    *account.write().unwrap() = state;
+
+                    cloned_sender.send(Event::Redraw).unwrap();
+                },
+                Err(err) => error!("sign_in: {}", err),
+            }
+        });
+    }
+
+    fn run_command(&mut self, line: &str) {
+        let mut dev = match self.selected_device() {
+            Some(dev) => dev,
+            None => {
+                error!("run_command: No device selected");
+
+                return;
+            }
+        };
+
+        /* Raw commands are validated before they reach the device */
+        if line.starts_with("heos://") {
+            let cmd = match line.parse::<HeosCommand>() {
+                Ok(cmd) => cmd,
+                Err(err) => {
+                    error!("run_command: {}", err);
+
+                    return;
+                }
+            };
+
+            tokio::spawn(async move {
+                info!("run_command: raw cmd={}", cmd.name());
+
+                match dev.send_command(&cmd).await {
+                    Ok(reply) => info!("run_command: {:?}", reply),
+                    Err(err) => error!("run_command: {:?}", err),
+                }
+            });
+
+            return;
+        }
+
+        let mut args = line.split_whitespace();
+
+        match (args.next(), args.next(), args.next()) {
+            (Some("stream"), Some(url), None) => {
+                let url = url.to_string();
+
+                tokio::spawn(async move {
+                    info!("run_command: stream url={}", url);
+
+                    if let Err(err) = dev.play_stream(&url).await {
+                        error!("run_command: {:?}", err);
+                    }
+                });
+            },
+            (Some("input"), Some(name), source_name) => {
+                let input = match name.parse::<HeosInput>() {
+                    Ok(input) => input,
+                    Err(err) => {
+                        error!("run_command: {:?}", err);
+
+                        return;
+                    }
+                };
+
+                /* Inputs of other players are addressed by their name */
+                let source_player_id = match source_name {
+                    Some(source_name) => match self.dev_list.read().unwrap().iter()
+                        .find(|dev| dev.name == source_name)
+                    {
+                        Some(source_dev) => Some(source_dev.player_id.clone()),
+                        None => {
+                            error!("run_command: Device `{}` not found", source_name);
+
+                            return;
+                        }
+                    },
+                    None => None,
+                };
 
-                let cmd = HeosCommand::new()
-                    .group("group")
-                    .cmd("toggle_mute");
+                tokio::spawn(async move {
+                    info!("run_command: input={}, spid={:?}", input, source_player_id);
 
-                let reply = group.send_command(&cmd).await.unwrap();
+                    if let Err(err) = dev.play_input(input, source_player_id.as_deref()).await {
+                        error!("run_command: {:?}", err);
+                    }
+                });
+            },
+            _ => error!("run_command: Unknown command `{}`", line),
+        }
+    }
 
-                if let HeosReply::Mute(success, _) = reply {
-                    info!("toggle_mute: success={}", success);
+    fn open_search(&mut self) {
+        /* Search the source the browser is in or the selected one */
+        let maybe_source = {
+            let read_browser = self.browser.read().unwrap();
+
+            read_browser.levels.iter().rev()
+                .find_map(|level| level.source_id.clone().map(|source_id| (source_id, level.name.clone())))
+                .or_else(|| match self.selected_browse_item() {
+                    Some((_, _, HeosBrowseItem::Source(source))) => Some((source.source_id, source.name)),
+                    _ => None,
+                })
+        };
+
+        let (source_id, source_name) = match maybe_source {
+            Some(source) => source,
+            None => {
+                error!("open_search: No music source selected in browser");
+
+                return;
+            }
+        };
+
+        let mut dev = match self.browse_device() {
+            Some(dev) => dev,
+            None => {
+                error!("open_search: No device found");
+
+                return;
+            }
+        };
+
+        *self.search_prompt.write().unwrap() = Some(SearchPrompt {
+            source_id: source_id.clone(),
+            source_name,
+            ..Default::default()
+        });
+
+        let search_prompt = Arc::clone(&self.search_prompt);
+        let cloned_sender = self.sender.clone();
+
+        tokio::spawn(async move {
+            match dev.get_search_criteria(&source_id).await {
+                Ok(criteria) => {
+                    if let Some(prompt) = search_prompt.write().unwrap().as_mut() {
+                        prompt.criteria = criteria;
+                    }
 
                     cloned_sender.send(Event::Redraw).unwrap();
-                } else if let HeosReply::Error(success, command, message) = reply {
-                    error!("toggle_group_mute: success={}, command={:?}, message={:?}",
-                        success, command, message);
+                },
+                Err(err) => error!("open_search: {:?}", err),
+            }
+        });
+    }
+
+    fn start_search(&mut self, prompt: SearchPrompt) {
+        let criteria = match prompt.criteria.get(prompt.selected) {
+            Some(criteria) if !prompt.query.is_empty() => criteria,
+            _ => return,
+        };
+
+        info!("start_search: source={}, criteria={}, query={}",
+            prompt.source_name, criteria, prompt.query);
+
+        let mut write_browser = self.browser.write().unwrap();
+
+        write_browser.levels.push(BrowseLevel {
+            name: format!("{} {}: {}", prompt.source_name, criteria, prompt.query),
+            source_id: Some(prompt.source_id.clone()),
+            container_id: criteria.result_container_id(&prompt.query),
+            is_loading: true,
+            search: Some(BrowseSearch {
+                criteria_id: criteria.criteria_id.clone(),
+                query: prompt.query.clone(),
+            }),
+            parent_selected: self.browser_list_state.selected(),
+            ..Default::default()
+        });
+
+        let depth = write_browser.levels.len() - 1;
+
+        drop(write_browser);
+
+        self.focus_state = Focus::Browser;
+        self.browser_list_state.select(None);
+        self.load_browse_page(depth, 0);
+    }
+
+    fn selected_queue_item(&self) -> Option<(HeosDevice, u32)> {
+        if !matches!(self.focus_state, Focus::Queue) {
+            return None;
+        }
+
+        let queue_id = self.queue_list_state.selected()
+            .and_then(|i| self.queue.read().unwrap().items.get(i).map(|item| item.queue_id))?;
+
+        self.selected_device().map(|dev| (dev, queue_id))
+    }
+
+    fn load_queue(&mut self) {
+        let player_id = self.selected_device()
+            .map(|dev| dev.player_id)
+            .unwrap_or_default();
+
+        let mut write_queue = self.queue.write().unwrap();
+
+        if write_queue.player_id == player_id {
+            return;
+        }
+
+        /* Drop stale items right away, refresh fills them in later */
+        write_queue.player_id = player_id;
+        write_queue.items.clear();
+
+        drop(write_queue);
+
+        self.queue_list_state.select(None);
+
+        if let Some(dev) = self.selected_device() {
+            tokio::spawn(refresh_queue(dev, Arc::clone(&self.queue), self.sender.clone()));
+        }
+    }
+
+    fn play_queue_item(&mut self) {
+        if let Some((mut dev, queue_id)) = self.selected_queue_item() {
+            tokio::spawn(async move {
+                info!("play_queue_item: qid={}", queue_id);
+
+                if let Err(err) = dev.play_queue(queue_id).await {
+                    error!("play_queue_item: {:?}", err);
+                }
+            });
+        }
+    }
+
+    fn remove_queue_item(&mut self) {
+        if let Some((mut dev, queue_id)) = self.selected_queue_item() {
+            tokio::spawn(async move {
+                info!("remove_queue_item: qid={}", queue_id);
+
+                if let Err(err) = dev.remove_from_queue(&[queue_id]).await {
+                    error!("remove_queue_item: {:?}", err);
                 }
             });
         }
     }
+
+    fn move_queue_item(&mut self, offset: i32) {
+        if let Some((mut dev, queue_id)) = self.selected_queue_item() {
+            let len = self.queue.read().unwrap().items.len() as i32;
+            let dest_queue_id = queue_id as i32 + offset;
+
+            if !(1..=len).contains(&dest_queue_id) {
+                return;
+            }
+
+            /* Keep the moved item selected */
+            if 0 > offset {
+                self.queue_list_state.select_previous();
+            } else {
+                self.queue_list_state.select_next();
+            }
+
+            tokio::spawn(async move {
+                info!("move_queue_item: qid={}, dqid={}", queue_id, dest_queue_id);
+
+                if let Err(err) = dev.move_queue_item(&[queue_id], dest_queue_id as u32).await {
+                    error!("move_queue_item: {:?}", err);
+                }
+            });
+        }
+    }
+
+    fn clear_queue(&mut self) {
+        if let Some((mut dev, _)) = self.selected_queue_item() {
+            tokio::spawn(async move {
+                info!("clear_queue");
+
+                if let Err(err) = dev.clear_queue().await {
+                    error!("clear_queue: {:?}", err);
+                }
+            });
+        }
+    }
+}
+
+pub(crate) async fn refresh_queue(mut dev: HeosDevice, queue: Arc<RwLock<Queue>>,
+                                  sender: mpsc::UnboundedSender<Event>)
+{
+    let mut items = Vec::new();
+
+    /* Fetch page-wise until the device runs out of items */
+    loop {
+        let start = items.len() as u32;
+
+        match dev.get_queue(start..=start + QUEUE_PAGE_SIZE - 1).await {
+            Ok(page) => {
+                let is_last = (page.len() as u32) < QUEUE_PAGE_SIZE;
+
+                items.extend(page);
+
+                if is_last {
+                    break;
+                }
+            },
+            Err(err) => {
+                error!("refresh_queue: {:?}", err);
+
+                return;
+            }
+        }
+    }
+
+    info!("refresh_queue: Loaded nitems={} for {}", items.len(), dev);
+
+    let mut write_queue = queue.write().unwrap();
+
+    /* Selection might have changed in the meantime */
+    if write_queue.player_id == dev.player_id {
+        write_queue.items = items;
+
+        sender.send(Event::Redraw).unwrap();
+    }
+}
+
+async fn fetch_browse_page(mut dev: HeosDevice, browser: Arc<RwLock<Browser>>, depth: usize,
+                           start: u32, sender: mpsc::UnboundedSender<Event>)
+{
+    let (source_id, container_id, search) = match browser.read().unwrap().levels.get(depth) {
+        Some(level) => (level.source_id.clone(), level.container_id.clone(), level.search.clone()),
+        None => return,
+    };
+
+    let range = start..=start + BROWSE_PAGE_SIZE - 1;
+
+    let res = match (source_id.as_deref(), search.as_ref()) {
+        (Some(source_id), Some(search)) => dev.search(source_id, &search.criteria_id,
+                                                      &search.query, Some(range)).await
+            .map(|result| (result.items, result.count)),
+        (Some(source_id), None) => dev.browse(source_id, container_id.as_deref(),
+                                      Some(range)).await
+            .map(|result| (result.items, result.count)),
+        (None, _) => dev.get_music_sources().await
+            .map(|sources| {
+                let count = sources.len() as u32;
+
+                (sources.into_iter().map(HeosBrowseItem::Source).collect(), count)
+            }),
+    };
+
+    let mut write_browser = browser.write().unwrap();
+
+    /* User might have navigated elsewhere in the meantime */
+    let level = match write_browser.levels.get_mut(depth) {
+        Some(level) if level.source_id == source_id && level.container_id == container_id
+            && level.search == search && level.items.len() as u32 == start => level,
+        _ => return,
+    };
+
+    level.is_loading = false;
+
+    match res {
+        Ok((items, count)) => {
+            info!("fetch_browse_page: Loaded nitems={} of {} for {}", items.len(), count, level.name);
+
+            level.items.extend(items);
+            level.count = count;
+        },
+        Err(err) => error!("fetch_browse_page: {:?}", err),
+    }
+
+    sender.send(Event::Redraw).unwrap();
+}
+
+pub(crate) async fn refresh_favorites(mut dev: HeosDevice, favorites: Arc<RwLock<Vec<HeosMediaItem>>>,
+                                      sender: mpsc::UnboundedSender<Event>)
+{
+    match dev.get_favorites().await {
+        Ok(items) => {
+            info!("refresh_favorites: Loaded nitems={} from {}", items.len(), dev);
+
+            *favorites.write().unwrap() = items;
+
+            sender.send(Event::Redraw).unwrap();
+        },
+        Err(err) => error!("refresh_favorites: {:?}", err),
+    }
+}
+
+pub(crate) async fn refresh_account(mut dev: HeosDevice, account: Arc<RwLock<AccountState>>,
+                                    sender: mpsc::UnboundedSender<Event>)
+{
+    match dev.check_account().await {
+        Ok(state) => {
+            info!("refresh_account: Account is {}", state);
+
+            *account.write().unwrap() = state;
+
+            sender.send(Event::Redraw).unwrap();
+        },
+        Err(err) => error!("refresh_account: {:?}", err),
+    }
 }
@@ -9,16 +9,20 @@
 /// See the file LICENSE for details.
 ///
 
+use std::time::Duration;
 use crossterm::event::{Event as CrosstermEvent, KeyEvent};
 use futures::{FutureExt, StreamExt};
 use tokio::sync::mpsc;
 
 use crate::app::AppResult;
 
+const TICK_RATE: Duration = Duration::from_secs(1);
+
 #[allow(dead_code)]
 #[derive(Clone, Copy, Debug)]
 pub enum Event {
     Redraw,
+    Tick,
     Key(KeyEvent),
     Resize(u16, u16),
 }
@@ -38,6 +42,7 @@ impl EventHandler {
 
         let handler = tokio::spawn(async move {
             let mut reader = crossterm::event::EventStream::new();
+            let mut ticker = tokio::time::interval(TICK_RATE);
 
             loop {
                 let crossterm_event = reader.next().fuse();
@@ -47,6 +52,11 @@ impl EventHandler {
                         break;
                   }
 
+                  /* Keep interpolated progress moving */
+                  _ = ticker.tick() => {
+                        cloned_sender.send(Event::Tick).unwrap();
+                  }
+
                   Some(Ok(evt)) = crossterm_event => {
                         match evt {
                             CrosstermEvent::Key(key) => {
@@ -12,16 +12,16 @@
 use crate::app::AppResult;
 use crate::events::{Event, EventHandler};
 use crate::tui::Tui;
-use app::App;
+use app::{App, Queue};
 use futures::pin_mut;
 use futures_util::StreamExt;
 use heos_lib::heos_command::{HeosCommand, HeosCommandHandler};
-use heos_lib::{Heos, HeosDevice, HeosGroup, HeosReply};
+use heos_lib::{AccountState, ConnectionState, Heos, HeosDevice, HeosEvent, HeosGroup, HeosMediaItem, HeosReply};
 use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
 use std::io;
 use std::sync::{Arc, RwLock};
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use tokio::sync::mpsc::UnboundedSender;
 
 mod app;
@@ -43,17 +43,24 @@ async fn main() -> AppResult<()> {
     /* Create swap list */
     let dev_orig_list = Arc::new(RwLock::new(Vec::<HeosDevice>::new()));
     let group_orig_list = Arc::new(RwLock::new(Vec::<HeosGroup>::new()));
+    let queue = Arc::new(RwLock::new(Queue::default()));
+    let favorites = Arc::new(RwLock::new(Vec::<HeosMediaItem>::new()));
+    let account = Arc::new(RwLock::new(AccountState::default()));
 
-    let mut app = App::new(Arc::clone(&dev_orig_list), Arc::clone(&group_orig_list), events.sender.clone());
+    let mut app = App::new(Arc::clone(&dev_orig_list), Arc::clone(&group_orig_list),
+                           Arc::clone(&queue), Arc::clone(&favorites), Arc::clone(&account),
+                           events.sender.clone());
 
-    tokio::spawn(start_discovery(Arc::clone(&dev_orig_list), Arc::clone(&group_orig_list), events.sender.clone()));
+    tokio::spawn(start_discovery(Arc::clone(&dev_orig_list), Arc::clone(&group_orig_list),
+                                 Arc::clone(&queue), Arc::clone(&favorites), Arc::clone(&account),
+                                 events.sender.clone()));
 
     /* Kick off main loop */
     while app.is_running {
         tui.draw(&mut app)?;
 
         match events.next().await? {
-            Event::Redraw => tui.draw(&mut app)?,
+            Event::Redraw | Event::Tick => tui.draw(&mut app)?,
             Event::Key(key_event) => app.handle_key_events(key_event)?,
             _ => {}
         }
@@ -64,14 +71,16 @@ async fn main() -> AppResult<()> {
     Ok(())
 }
 
-async fn start_discovery(dev_list: Arc<RwLock<Vec<HeosDevice>>>, group_list: Arc<RwLock<Vec<HeosGroup>>>, cloned_sender: UnboundedSender<Event>) {
+async fn start_discovery(dev_list: Arc<RwLock<Vec<HeosDevice>>>, group_list: Arc<RwLock<Vec<HeosGroup>>>,
+                         queue: Arc<RwLock<Queue>>, favorites: Arc<RwLock<Vec<HeosMediaItem>>>,
+                         account: Arc<RwLock<AccountState>>, cloned_sender: UnboundedSender<Event>) {
     let devices = Heos::discover().await
         .expect("To discover devices");
     pin_mut!(devices);
 
     info!("discovery: Start");
 
-    let mut cmd = HeosCommand::new()
+    let cmd = HeosCommand::new()
         .group("player")
         .cmd("get_players");
 
@@ -81,7 +90,7 @@ async fn start_discovery(dev_list: Arc<RwLock<Vec<HeosDevice>>>, group_list: Arc
         info!("discovery: Requesting known devices from {}", dev);
 
         /* Ask first device for other known devices */
-        let mut reply = dev.send_command(&cmd).await
+        let reply = dev.send_command(&cmd).await
             .expect("To send command");
 
         if let HeosReply::Players(success, mut devices) = reply {
@@ -94,6 +103,18 @@ async fn start_discovery(dev_list: Arc<RwLock<Vec<HeosDevice>>>, group_list: Arc
                     info!("discovery: Updated volume for {} ({:?})", dev, res);
                 }
 
+                for dev in &mut devices {
+                    let res = dev.update_mute().await;
+
+                    info!("discovery: Updated mute for {} ({:?})", dev, res);
+                }
+
+                for dev in &mut devices {
+                    let res = dev.update_play_mode().await;
+
+                    info!("discovery: Updated play mode for {} ({:?})", dev, res);
+                }
+
                 for dev in &mut devices {
                     let res = dev.update_media().await;
 
@@ -115,47 +136,233 @@ async fn start_discovery(dev_list: Arc<RwLock<Vec<HeosDevice>>>, group_list: Arc
         cloned_sender.send(Event::Redraw).unwrap();
 
         /* Ask first device for known groups */
-        cmd = HeosCommand::new()
-            .group("player")
-            .cmd("get_groups");
+        refresh_groups(dev.clone(), Arc::clone(&dev_list), Arc::clone(&group_list), cloned_sender.clone()).await;
 
-        reply = dev.send_command(&cmd).await.expect("To send command");
+        /* Fetch favorites for the presets row */
+        app::refresh_favorites(dev.clone(), Arc::clone(&favorites), cloned_sender.clone()).await;
 
-        if let HeosReply::Groups(success, mut groups) = reply {
-            if success {
-                debug!("start_discovery: Found ngroups={}", groups.len());
+        /* Show whether services need a sign in */
+        app::refresh_account(dev.clone(), Arc::clone(&account), cloned_sender.clone()).await;
+
+        /* Listen for changes made by other controllers */
+        tokio::spawn(start_events(dev, Arc::clone(&dev_list), Arc::clone(&group_list),
+                                  Arc::clone(&queue), Arc::clone(&favorites), Arc::clone(&account),
+                                  cloned_sender.clone()));
+
+        break;
+    }
+}
+
+async fn refresh_groups(mut dev: HeosDevice, dev_list: Arc<RwLock<Vec<HeosDevice>>>,
+                        group_list: Arc<RwLock<Vec<HeosGroup>>>, cloned_sender: UnboundedSender<Event>) {
+    let cmd = HeosCommand::new()
+        .group("player")
+        .cmd("get_groups");
+
+    let reply = match dev.send_command(&cmd).await {
+        Ok(reply) => reply,
+        Err(err) => {
+            error!("refresh_groups: {:?}", err);
+
+            return;
+        }
+    };
+
+    if let HeosReply::Groups(success, mut groups) = reply {
+        if success {
+            debug!("refresh_groups: Found ngroups={}", groups.len());
 
-                /* Find base url for leader if any */
-                for group in &mut groups {
-                    info!("start_discovery: Found group {}", group);
+            /* Find base url for leader if any */
+            for group in &mut groups {
+                info!("refresh_groups: Found group {}", group);
 
-                    if let Some(leader) = group.leader.as_mut() {
-                        for dev in dev_list.read().unwrap().iter() {
-                            if dev == leader {
-                                leader.base_url = dev.base_url.clone();
-                            }
+                if let Some(leader) = group.leader.as_mut() {
+                    for dev in dev_list.read().unwrap().iter() {
+                        if dev == leader {
+                            leader.base_url = dev.base_url.clone();
                         }
                     }
                 }
+            }
+
+            for group in &mut groups {
+                let res = group.update_volume().await;
+
+                info!("refresh_groups: Updated volume for {} ({:?})", group, res);
+            }
+
+            for group in &mut groups {
+                let res = group.update_mute().await;
+
+                info!("refresh_groups: Updated mute for {} ({:?})", group, res);
+            }
+
+            /* Replace vec */
+            let mut write_list = group_list.write().unwrap();
+
+            let _ = std::mem::replace(&mut *write_list, groups);
+
+            cloned_sender.send(Event::Redraw).unwrap();
+        }
+    } else if let HeosReply::Error(success, command, message) = reply {
+        error!("refresh_groups: success={}, command={:?}, message={:?}",
+                    success, command, message);
+    }
+}
+
+async fn start_events(mut dev: HeosDevice, dev_list: Arc<RwLock<Vec<HeosDevice>>>, group_list: Arc<RwLock<Vec<HeosGroup>>>,
+                      queue: Arc<RwLock<Queue>>, favorites: Arc<RwLock<Vec<HeosMediaItem>>>,
+                      account: Arc<RwLock<AccountState>>, cloned_sender: UnboundedSender<Event>) {
+    let events = match dev.events().await {
+        Ok(events) => events,
+        Err(err) => {
+            error!("events: Failed to register for change events: {:?}", err);
+
+            return;
+        }
+    };
+    pin_mut!(events);
+
+    /* Connection heals itself and keeps the event stream alive, just report it */
+    if let Some(mut state) = dev.connection_state() {
+        let state_sender = cloned_sender.clone();
 
-                for group in &mut groups {
-                    let res = group.update_volume().await;
+        tokio::spawn(async move {
+            while state.changed().await.is_ok() {
+                let current = *state.borrow_and_update();
 
-                    info!("discovery: Updated volume for {} ({:?})", group, res);
+                match current {
+                    ConnectionState::Connected => info!("events: Connection {}", current),
+                    _ => warn!("events: Connection {}", current),
                 }
 
-                /* Replace vec */
+                let _ = state_sender.send(Event::Redraw);
+            }
+        });
+    }
+
+    info!("events: Start");
+
+    while let Some(event) = events.next().await {
+        debug!("events: Received {:?}", event);
+
+        match event {
+            HeosEvent::PlayerVolumeChanged { player_id, level, mute } => {
+                let mut write_list = dev_list.write().unwrap();
+
+                if let Some(dev) = write_list.iter_mut().find(|dev| dev.player_id == player_id) {
+                    dev.volume = level;
+                    dev.muted = mute;
+                }
+            },
+            HeosEvent::GroupVolumeChanged { group_id, level, mute } => {
                 let mut write_list = group_list.write().unwrap();
 
-                let _ = std::mem::replace(&mut *write_list, groups);
+                if let Some(group) = write_list.iter_mut().find(|group| group.group_id == group_id) {
+                    group.volume = level;
+                    group.muted = mute;
+                }
+            },
+            HeosEvent::PlayerNowPlayingChanged { player_id } => {
+                /* Don't hold the lock while waiting for the device */
+                let maybe_dev = dev_list.read().unwrap().iter()
+                    .find(|dev| dev.player_id == player_id).cloned();
 
-                cloned_sender.send(Event::Redraw).unwrap();
+                if let Some(mut dev) = maybe_dev {
+                    let res = dev.update_media().await;
+
+                    info!("events: Updated media for {} ({:?})", dev, res);
+
+                    let mut write_list = dev_list.write().unwrap();
+
+                    if let Some(listed_dev) = write_list.iter_mut().find(|listed_dev| **listed_dev == dev) {
+                        listed_dev.media = dev.media;
+
+                        /* Start over right away, progress events only arrive every few seconds */
+                        match listed_dev.media.as_ref().map(|media| media.duration) {
+                            Some(duration) => listed_dev.update_progress(0, duration
+                                .map_or(0, |duration| duration.as_millis() as u64)),
+                            None => listed_dev.progress = None,
+                        }
+                    }
+                }
+            },
+            HeosEvent::GroupsChanged => {
+                let maybe_dev = dev_list.read().unwrap().first().cloned();
+
+                if let Some(dev) = maybe_dev {
+                    tokio::spawn(refresh_groups(dev, Arc::clone(&dev_list), Arc::clone(&group_list),
+                                                cloned_sender.clone()));
+                }
+            },
+            HeosEvent::SourcesChanged => {
+                /* Favorites are a source as well */
+                let maybe_dev = dev_list.read().unwrap().first().cloned();
+
+                if let Some(dev) = maybe_dev {
+                    tokio::spawn(app::refresh_favorites(dev, Arc::clone(&favorites), cloned_sender.clone()));
+                }
+            },
+            HeosEvent::PlayerQueueChanged { player_id } => {
+                /* Only the shown queue is of interest */
+                let maybe_dev = match queue.read().unwrap().player_id == player_id {
+                    true => dev_list.read().unwrap().iter()
+                        .find(|dev| dev.player_id == player_id).cloned(),
+                    false => None,
+                };
+
+                if let Some(dev) = maybe_dev {
+                    tokio::spawn(app::refresh_queue(dev, Arc::clone(&queue), cloned_sender.clone()));
+                }
+            },
+            HeosEvent::PlayerStateChanged { player_id, state } => {
+                info!("events: Player state changed: pid={}, state={}", player_id, state);
+
+                let mut write_list = dev_list.write().unwrap();
+
+                if let Some(dev) = write_list.iter_mut().find(|dev| dev.player_id == player_id) {
+                    dev.update_play_state(state);
+                }
+            },
+            HeosEvent::PlayerNowPlayingProgress { player_id, cur_pos, duration } => {
+                let mut write_list = dev_list.write().unwrap();
+
+                if let Some(dev) = write_list.iter_mut().find(|dev| dev.player_id == player_id) {
+                    dev.update_progress(cur_pos, duration);
+                }
+            },
+            HeosEvent::RepeatModeChanged { player_id, repeat } => {
+                let mut write_list = dev_list.write().unwrap();
+
+                if let Some(dev) = write_list.iter_mut().find(|dev| dev.player_id == player_id) {
+                    dev.repeat = repeat;
+                }
+            },
+            HeosEvent::ShuffleModeChanged { player_id, shuffle } => {
+                let mut write_list = dev_list.write().unwrap();
+
+                if let Some(dev) = write_list.iter_mut().find(|dev| dev.player_id == player_id) {
+                    dev.shuffle = shuffle;
+                }
+            },
+            HeosEvent::UserChanged { signed_in, user_name } => {
+                *account.write().unwrap() = match signed_in {
+                    true => AccountState::SignedIn {
+                        user_name: user_name.unwrap_or_default(),
+                    },
+                    false => AccountState::SignedOut,
+                };
+            },
+            HeosEvent::PlayerPlaybackError { player_id, error } => {
+                error!("events: Playback error: pid={}, error={}", player_id, error);
+            },
+            event => {
+                debug!("events: Unhandled event {:?}", event);
             }
-        } else if let HeosReply::Error(success, command, message) = reply {
-            error!("start_discovery: success={}, command={:?}, message={:?}",
-                        success, command, message);
         }
 
-        break;
+        cloned_sender.send(Event::Redraw).unwrap();
     }
+
+    info!("events: Stop");
 }
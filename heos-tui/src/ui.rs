@@ -13,12 +13,13 @@ use ratatui::{style::{Color, Style}, symbols, widgets::{Block, Paragraph}};
 use ratatui::buffer::Buffer;
 use ratatui::layout::{Constraint, Layout, Rect};
 use ratatui::prelude::{Line, Modifier, StatefulWidget, Stylize, Widget};
-use ratatui::style::palette::tailwind::{GREEN, SLATE};
+use ratatui::style::palette::tailwind::{BLUE, GREEN, SLATE};
 use ratatui::text::Span;
-use ratatui::widgets::{Borders, Gauge, HighlightSpacing, List, ListItem, Padding, Wrap};
+use ratatui::widgets::{Borders, Clear, Gauge, HighlightSpacing, List, ListItem, ListState, Padding, Wrap};
 use tui_logger::{TuiLoggerLevelOutput, TuiLoggerWidget};
-use heos_lib::{HeosDevice, HeosGroup};
+use heos_lib::{HeosBrowseItem, HeosDevice, HeosGroup};
 use std::cmp::PartialEq;
+use std::time::Duration;
 use ratatui::style::palette::material::RED;
 use crate::app::{App, Focus};
 
@@ -33,26 +34,62 @@ const ATTENTION_TEXT_FG_COLOR: Color = RED.c200;
 const NORMAL_ROW_BG_COLOR: Color = SLATE.c950;
 const ALT_ROW_BG_COLOR: Color = SLATE.c900;
 const VOLUME_GAUGE_COLOR: Color = GREEN.c100;
+const PROGRESS_GAUGE_COLOR: Color = BLUE.c300;
 
 // Icons for UI taken from https://gist.github.com/nicolasdao/8f0220d050f585be1b56cc615ef6c12e
 const ICON_ID: &'static str = "🆔 ";
 const ICON_MODEL: &'static str = "™️";
 const ICON_URL: &'static str = "🔗";
 const ICON_PLAY: &'static str = "▶";
+const ICON_MODE: &'static str = "🔁";
 const ICON_VOL_ON: &'static str = "🔈";
 const ICON_VOL_OFF: &'static str = "🔇";
 const ICON_DEV_NAME: &'static str = "📻";
 const ICON_GROUP_NAME: &'static str = "📻";
+const ICON_SOURCE: &'static str = "🎵";
+const ICON_CONTAINER: &'static str = "📁";
+const ICON_MEDIA: &'static str = "♪";
+const ICON_MARKED: &'static str = "✔";
+const ICON_OPTIONS: &'static str = "⚙";
+const ICON_ACCOUNT: &'static str = "👤";
+const ICON_CONNECTION: &'static str = "🔌";
 
 // Text in UI
-const TEXT_STATUS: &'static str = "Use ↓ /↑ to move, ← /→  to lower/raise volume, g/d to select lists, p to play, s to stop, m toggle mute.";
+const TEXT_STATUS: &'static str = "Use ↓ /↑ to move, ← /→  to lower/raise volume, p to play, s to stop, ? for help, q to quit.";
+
+const TEXT_HELP: &'static [(&'static str, &'static str)] = &[
+    ("↓ /↑ , Home/End", "move, Esc to deselect"),
+    ("← /→", "lower/raise volume"),
+    ("d/g/u/b", "select devices/groups/queue/browser"),
+    ("p/s", "play/stop"),
+    ("m/r/z", "toggle mute, cycle repeat, toggle shuffle"),
+    ("1-9", "play preset"),
+    ("⏎ /⌫", "jump/open, back"),
+    ("/", "search"),
+    ("P/n/a/R", "play now/next/add/replace"),
+    ("x/J/K/X", "remove, move, clear queue"),
+    ("o", "media options"),
+    ("␣", "mark device"),
+    ("G/U", "group marked, ungroup"),
+    ("A", "sign in/out"),
+    (":", "commands"),
+    ("q", "quit"),
+];
+
+const TEXT_PALETTE: &'static str = "stream <url> | input <name> [<device>] | heos://<group>/<cmd>?<attrs>";
 
 const HEADER_DEVICE_LIST: &'static str = "Device List (d)";
 const HEADER_GROUP_LIST: &'static str = "Group List (g)";
+const HEADER_QUEUE: &'static str = "Queue (u)";
+const HEADER_BROWSER: &'static str = "Browser (b)";
 const HEADER_LOGS: &'static str = "Heos Logs";
 
 const HEADER_DEVICE_INFO: &'static str = "Device Info";
 const HEADER_VOLUME: &'static str = "Volume";
+const HEADER_PROGRESS: &'static str = "Progress";
+const HEADER_OPTIONS: &'static str = "Options";
+const HEADER_HELP: &'static str = "Help (any key to close)";
+const HEADER_SIGN_IN: &'static str = "Sign in (⇥ to switch, ⏎ to sign in, Esc to cancel)";
 
 impl Widget for &mut App {
     fn render(self, area: Rect, buf: &mut Buffer) {
@@ -72,29 +109,98 @@ impl Widget for &mut App {
             Layout::vertical([Constraint::Fill(3), Constraint::Fill(1), Constraint::Fill(2)])
                 .areas(item_area);
 
-        render_header(header_area, buf);
-        render_footer(footer_area, buf);
+        let [info_col_area, queue_area, browser_area] =
+            Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1), Constraint::Fill(1)])
+                .areas(text_area);
+
+        let [info_area, progress_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(3)]).areas(info_col_area);
+
+        render_header(self, header_area, buf);
+        render_footer(self, footer_area, buf);
 
         render_dev_list(self, dev_list_area, buf);
         render_group_list(self, group_list_area, buf);
 
-        render_selected_item(self, text_area, buf);
+        render_selected_item(self, info_area, buf);
+        render_progress(self, progress_area, buf);
+        render_queue(self, queue_area, buf);
+        render_browser(self, browser_area, buf);
         render_gauge(self, gauge_area, buf);
         render_logger(self, log_area, buf);
+
+        if self.options_menu.is_some() {
+            render_options_menu(self, text_area, buf);
+        }
+
+        if self.sign_in_prompt.is_some() {
+            render_sign_in_prompt(self, text_area, buf);
+        }
+
+        if self.show_help {
+            render_help(main_area, buf);
+        }
     }
 }
 
-fn render_header(area: Rect, buf: &mut Buffer) {
-    Paragraph::new("Heos devices")
-        .bold()
+fn render_header(app: &App, area: Rect, buf: &mut Buffer) {
+    let favorites = app.favorites.read().unwrap();
+
+    /* Number keys play the first nine presets */
+    let presets: Vec<Span> = favorites.iter()
+        .take(9)
+        .enumerate()
+        .flat_map(|(i, favorite)| [
+            Span::styled(format!(" {} ", i + 1), SELECTED_STYLE),
+            Span::styled(format!(" {}  ", favorite.name), NORMAL_TEXT_FG_COLOR),
+        ])
+        .collect();
+
+    let account = app.account.read().unwrap();
+
+    let account_style = match account.is_signed_in() {
+        true => ACTIVE_TEXT_FG_COLOR,
+        false => ATTENTION_TEXT_FG_COLOR,
+    };
+
+    Paragraph::new(vec![
+        Line::from(vec![
+            Span::raw("Heos devices").bold(),
+            Span::styled(format!("  {} {}", ICON_ACCOUNT, account), account_style),
+        ]),
+        Line::from(presets),
+    ])
         .centered()
         .render(area, buf);
 }
 
-fn render_footer(area: Rect, buf: &mut Buffer) {
-    let lines = Line::from(vec![
-        Span::raw(TEXT_STATUS),
-    ]);
+fn render_footer(app: &App, area: Rect, buf: &mut Buffer) {
+    if let Some(palette) = app.palette.as_ref() {
+        let lines = Line::from(vec![
+            Span::styled(format!("{} :", TEXT_PALETTE), ACTIVE_TEXT_FG_COLOR),
+            Span::raw(format!("{}▏", palette)),
+        ]);
+
+        Paragraph::new(lines)
+            .centered()
+            .render(area, buf);
+
+        return;
+    }
+
+    let lines = match app.search_prompt.read().unwrap().as_ref() {
+        Some(prompt) => Line::from(vec![
+            Span::styled(format!("Search {} by {} (⇥ to switch, ⏎ to search, Esc to cancel): ",
+                                 prompt.source_name,
+                                 prompt.criteria.get(prompt.selected)
+                                     .map_or("..".to_string(), |criteria| criteria.to_string())),
+                         ACTIVE_TEXT_FG_COLOR),
+            Span::raw(format!("{}▏", prompt.query)),
+        ]),
+        None => Line::from(vec![
+            Span::raw(TEXT_STATUS),
+        ]),
+    };
 
     Paragraph::new(lines)
         .centered()
@@ -123,11 +229,16 @@ fn render_dev_list(app: &mut App, area: Rect, buf: &mut Buffer) {
         .map(|(i, dev_item)| {
             let color = alternate_colors(i);
 
-            let line = match dev_item.volume {
-                x if 0 < x => Line::styled(
-                    format!("{:^5} {}", ICON_VOL_ON, dev_item.name), ACTIVE_TEXT_FG_COLOR),
-                _ => Line::styled(
-                    format!("{:^5} {}", ICON_VOL_OFF, dev_item.name), NORMAL_TEXT_FG_COLOR),
+            let mark = match app.marked.contains(&dev_item.player_id) {
+                true => ICON_MARKED,
+                false => " ",
+            };
+
+            let line = match dev_item.muted {
+                false => Line::styled(
+                    format!("{}{:^5} {}", mark, ICON_VOL_ON, dev_item.name), ACTIVE_TEXT_FG_COLOR),
+                true => Line::styled(
+                    format!("{}{:^5} {}", mark, ICON_VOL_OFF, dev_item.name), NORMAL_TEXT_FG_COLOR),
             };
 
             ListItem::new(line).bg(color)
@@ -175,8 +286,12 @@ fn render_group_list(app: &mut App, area: Rect, buf: &mut Buffer) {
         .map(|(i, group_item)| {
             let color = alternate_colors(i);
 
-            let line = Line::styled(format!("{:^5} {}", "∑",
-                                            group_item.name), NORMAL_TEXT_FG_COLOR);
+            let line = match group_item.muted {
+                false => Line::styled(
+                    format!("{:^5} {} {}", "∑", ICON_VOL_ON, group_item.name), ACTIVE_TEXT_FG_COLOR),
+                true => Line::styled(
+                    format!("{:^5} {} {}", "∑", ICON_VOL_OFF, group_item.name), NORMAL_TEXT_FG_COLOR),
+            };
 
             ListItem::new(line).bg(color)
         })
@@ -196,6 +311,115 @@ fn render_group_list(app: &mut App, area: Rect, buf: &mut Buffer) {
     StatefulWidget::render(list, area, buf, &mut app.group_list_state);
 }
 
+fn render_queue(app: &mut App, area: Rect, buf: &mut Buffer) {
+    let style = match app.focus_state {
+        Focus::Queue => SELECTED_STYLE,
+        _ => HEADER_STYLE,
+    };
+
+    let block = Block::new()
+        .title(Line::raw(HEADER_QUEUE).centered())
+        .borders(Borders::all())
+        .border_set(symbols::border::PLAIN)
+        .border_style(style)
+        .bg(NORMAL_ROW_BG_COLOR);
+
+    /* Highlight what is playing right now */
+    let playing_qid = get_selected_device(app)
+        .and_then(|dev| dev.media)
+        .and_then(|media| media.queue_id);
+
+    let queue = app.queue.read().unwrap();
+
+    let mut items: Vec<ListItem> = queue.items
+        .iter()
+        .enumerate()
+        .map(|(i, queue_item)| {
+            let color = alternate_colors(i);
+
+            let line = match Some(queue_item.queue_id) == playing_qid {
+                true => Line::styled(format!("{:^3} {} - {} ({})", ICON_PLAY,
+                                             queue_item.song_title, queue_item.artist_title,
+                                             queue_item.album_title), ACTIVE_TEXT_FG_COLOR),
+                false => Line::styled(format!("{:^3} {} - {} ({})", queue_item.queue_id,
+                                              queue_item.song_title, queue_item.artist_title,
+                                              queue_item.album_title), NORMAL_TEXT_FG_COLOR),
+            };
+
+            ListItem::new(line).bg(color)
+        })
+        .collect();
+
+    /* Check whether list is empty */
+    if items.is_empty() {
+        items.push(ListItem::new("Queue is empty"));
+    }
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(SELECTED_STYLE)
+        .highlight_symbol(">")
+        .highlight_spacing(HighlightSpacing::Always);
+
+    StatefulWidget::render(list, area, buf, &mut app.queue_list_state);
+}
+
+fn render_browser(app: &mut App, area: Rect, buf: &mut Buffer) {
+    let style = match app.focus_state {
+        Focus::Browser => SELECTED_STYLE,
+        _ => HEADER_STYLE,
+    };
+
+    let browser = app.browser.read().unwrap();
+    let level = browser.levels.last();
+
+    let title = match level {
+        Some(level) => format!("{} - {}", HEADER_BROWSER, level.name),
+        None => HEADER_BROWSER.to_string(),
+    };
+
+    let block = Block::new()
+        .title(Line::raw(title).centered())
+        .borders(Borders::all())
+        .border_set(symbols::border::PLAIN)
+        .border_style(style)
+        .bg(NORMAL_ROW_BG_COLOR);
+
+    let mut items: Vec<ListItem> = level
+        .map(|level| level.items.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+        .map(|(i, browse_item)| {
+            let color = alternate_colors(i);
+
+            let icon = match browse_item {
+                HeosBrowseItem::Source(_) => ICON_SOURCE,
+                HeosBrowseItem::Container(_) => ICON_CONTAINER,
+                HeosBrowseItem::Media(_) => ICON_MEDIA,
+            };
+
+            ListItem::new(Line::styled(format!("{:^3} {}", icon, browse_item),
+                                       NORMAL_TEXT_FG_COLOR)).bg(color)
+        })
+        .collect();
+
+    /* Tell apart pending and empty containers */
+    match level {
+        Some(level) if level.is_loading => items.push(ListItem::new("Loading..")),
+        _ if items.is_empty() => items.push(ListItem::new("Nothing to browse")),
+        _ => {},
+    }
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(SELECTED_STYLE)
+        .highlight_symbol(">")
+        .highlight_spacing(HighlightSpacing::Always);
+
+    StatefulWidget::render(list, area, buf, &mut app.browser_list_state);
+}
+
 fn render_selected_item(app: &App, area: Rect, buf: &mut Buffer) {
     let title = title_block(HEADER_DEVICE_INFO);
     let style = Style::new()
@@ -209,9 +433,23 @@ fn render_selected_item(app: &App, area: Rect, buf: &mut Buffer) {
         lines.push(Line::styled(format!("{:^4} : {}", ICON_URL, dev.base_url), style));
         lines.push(Line::styled(format!("{:^4} : {}", ICON_ID, dev.player_id), style));
 
+        if let Some(state) = dev.connection_state() {
+            lines.push(Line::styled(format!("{:^4} : {}", ICON_CONNECTION, *state.borrow()), style));
+        }
+
+        lines.push(Line::styled(format!("{:^4} : repeat={}, shuffle={}", ICON_MODE,
+                                        dev.repeat, dev.shuffle), style));
+
         if let Some(media) = dev.media {
-            lines.push(Line::styled(format!("{:^5} : {} - {} ({})", ICON_PLAY,
-                                            media.artist_title, media.song_title, media.album_title), style));
+            lines.push(Line::styled(format!("{:^5} : {}", ICON_PLAY, media), style));
+            lines.push(Line::styled(format!("{:^4} : {}", ICON_SOURCE, media.source_type), style));
+
+            if !media.options.is_empty() {
+                lines.push(Line::styled(format!("{:^4} : {} (o)", ICON_OPTIONS, media.options.iter()
+                    .map(|option| option.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")), style));
+            }
         }
     } else if let Some(group) = get_selected_group(app) {
         lines.push(Line::styled(format!("{:^4} : {}", ICON_GROUP_NAME, group.name), style));
@@ -230,6 +468,114 @@ fn render_selected_item(app: &App, area: Rect, buf: &mut Buffer) {
         .render(area, buf);
 }
 
+fn render_options_menu(app: &App, area: Rect, buf: &mut Buffer) {
+    let menu = match app.options_menu.as_ref() {
+        Some(menu) => menu,
+        None => return,
+    };
+
+    let popup_area = popup_area(area, menu.options.len() as u16 + 2);
+
+    let items: Vec<ListItem> = menu.options.iter()
+        .map(|option| ListItem::new(option.to_string()))
+        .collect();
+
+    let list = List::new(items)
+        .block(title_block(HEADER_OPTIONS))
+        .highlight_style(SELECTED_STYLE)
+        .highlight_symbol(">")
+        .highlight_spacing(HighlightSpacing::Always);
+
+    let mut state = ListState::default().with_selected(Some(menu.selected));
+
+    Clear.render(popup_area, buf);
+    StatefulWidget::render(list, popup_area, buf, &mut state);
+}
+
+fn render_sign_in_prompt(app: &App, area: Rect, buf: &mut Buffer) {
+    let prompt = match app.sign_in_prompt.as_ref() {
+        Some(prompt) => prompt,
+        None => return,
+    };
+
+    let popup_area = popup_area(area, 4);
+
+    let field_style = |focused: bool| match focused {
+        true => ACTIVE_TEXT_FG_COLOR,
+        false => NORMAL_TEXT_FG_COLOR,
+    };
+
+    /* Never show the password */
+    let lines = vec![
+        Line::styled(format!("User     : {}{}", prompt.user_name,
+                             if prompt.password_focused { "" } else { "▏" }),
+                     field_style(!prompt.password_focused)),
+        Line::styled(format!("Password : {}{}", "*".repeat(prompt.password.chars().count()),
+                             if prompt.password_focused { "▏" } else { "" }),
+                     field_style(prompt.password_focused)),
+    ];
+
+    Clear.render(popup_area, buf);
+    Paragraph::new(lines)
+        .block(title_block(HEADER_SIGN_IN))
+        .render(popup_area, buf);
+}
+
+fn render_help(area: Rect, buf: &mut Buffer) {
+    let popup_area = popup_area(area, TEXT_HELP.len() as u16 + 2);
+
+    let lines: Vec<Line> = TEXT_HELP.iter()
+        .map(|(keys, text)| Line::from(vec![
+            Span::styled(format!("{:>16} ", keys), ACTIVE_TEXT_FG_COLOR),
+            Span::raw(*text),
+        ]))
+        .collect();
+
+    Clear.render(popup_area, buf);
+    Paragraph::new(lines)
+        .block(title_block(HEADER_HELP))
+        .render(popup_area, buf);
+}
+
+fn popup_area(area: Rect, height: u16) -> Rect {
+    /* Center popup over the given area */
+    let [_, popup_area, _] = Layout::horizontal([
+        Constraint::Fill(1), Constraint::Percentage(40), Constraint::Fill(1),
+    ]).areas(area);
+    let [_, popup_area, _] = Layout::vertical([
+        Constraint::Fill(1), Constraint::Length(height), Constraint::Fill(1),
+    ]).areas(popup_area);
+
+    popup_area
+}
+
+fn render_progress(app: &App, area: Rect, buf: &mut Buffer) {
+    let title = title_block(HEADER_PROGRESS);
+
+    let (ratio, label) = match get_selected_device(app).and_then(|dev| dev.progress) {
+        Some(progress) => (progress.ratio().clamp(0.0, 1.0),
+                           format!("{} / -{}", format_duration(progress.position()),
+                                   format_duration(progress.remaining()))),
+        None => (0.0, "--:-- / --:--".to_string()),
+    };
+
+    Gauge::default()
+        .block(title)
+        .gauge_style(PROGRESS_GAUGE_COLOR)
+        .ratio(ratio)
+        .label(label)
+        .render(area, buf);
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+
+    match secs / 3600 {
+        0 => format!("{:02}:{:02}", secs / 60, secs % 60),
+        hours => format!("{}:{:02}:{:02}", hours, (secs / 60) % 60, secs % 60),
+    }
+}
+
 fn render_gauge(app: &App, area: Rect, buf: &mut Buffer) {
     let title = title_block(HEADER_VOLUME);
 